@@ -1,11 +1,13 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use nprint_core::{MacroDef, MacroElem, expand_macro};
+use sv::script::op_codes::OP_DUP;
 
 fn bench_expand(c: &mut Criterion) {
     let def = MacroDef {
         name: "test".to_string(),
         param_count: 1,
         template: vec![MacroElem::Param(0), MacroElem::Op(OP_DUP)],
+        effect: None,
     };
     c.bench_function("expand_macro", |b| b.iter(|| expand_macro(&def, &[5])));
 }
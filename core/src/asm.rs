@@ -0,0 +1,288 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use num_bigint::BigInt;
+use num_traits::Num;
+use sv::script::op_codes::{OP_PUSHDATA1, OP_PUSHDATA2, OP_PUSHDATA4};
+
+use crate::{AsmError, AsmErrorKind, Instruction, Instructions, PushKind, ScriptNum, encode_push};
+
+/// Opcode names; the first entry for a byte is its canonical name, later
+/// ones are accepted aliases.
+const OPCODES: &[(&str, u8)] = &[
+    ("OP_0", 0x00),
+    ("OP_PUSHDATA1", 0x4c),
+    ("OP_PUSHDATA2", 0x4d),
+    ("OP_PUSHDATA4", 0x4e),
+    ("OP_1NEGATE", 0x4f),
+    ("OP_RESERVED", 0x50),
+    ("OP_1", 0x51),
+    ("OP_2", 0x52),
+    ("OP_3", 0x53),
+    ("OP_4", 0x54),
+    ("OP_5", 0x55),
+    ("OP_6", 0x56),
+    ("OP_7", 0x57),
+    ("OP_8", 0x58),
+    ("OP_9", 0x59),
+    ("OP_10", 0x5a),
+    ("OP_11", 0x5b),
+    ("OP_12", 0x5c),
+    ("OP_13", 0x5d),
+    ("OP_14", 0x5e),
+    ("OP_15", 0x5f),
+    ("OP_16", 0x60),
+    ("OP_NOP", 0x61),
+    ("OP_VER", 0x62),
+    ("OP_IF", 0x63),
+    ("OP_NOTIF", 0x64),
+    ("OP_VERIF", 0x65),
+    ("OP_VERNOTIF", 0x66),
+    ("OP_ELSE", 0x67),
+    ("OP_ENDIF", 0x68),
+    ("OP_VERIFY", 0x69),
+    ("OP_RETURN", 0x6a),
+    ("OP_TOALTSTACK", 0x6b),
+    ("OP_FROMALTSTACK", 0x6c),
+    ("OP_2DROP", 0x6d),
+    ("OP_2DUP", 0x6e),
+    ("OP_3DUP", 0x6f),
+    ("OP_2OVER", 0x70),
+    ("OP_2ROT", 0x71),
+    ("OP_2SWAP", 0x72),
+    ("OP_IFDUP", 0x73),
+    ("OP_DEPTH", 0x74),
+    ("OP_DROP", 0x75),
+    ("OP_DUP", 0x76),
+    ("OP_NIP", 0x77),
+    ("OP_OVER", 0x78),
+    ("OP_PICK", 0x79),
+    ("OP_ROLL", 0x7a),
+    ("OP_ROT", 0x7b),
+    ("OP_SWAP", 0x7c),
+    ("OP_TUCK", 0x7d),
+    ("OP_CAT", 0x7e),
+    ("OP_SPLIT", 0x7f),
+    ("OP_NUM2BIN", 0x80),
+    ("OP_BIN2NUM", 0x81),
+    ("OP_SIZE", 0x82),
+    ("OP_INVERT", 0x83),
+    ("OP_AND", 0x84),
+    ("OP_OR", 0x85),
+    ("OP_XOR", 0x86),
+    ("OP_EQUAL", 0x87),
+    ("OP_EQUALVERIFY", 0x88),
+    ("OP_RESERVED1", 0x89),
+    ("OP_RESERVED2", 0x8a),
+    ("OP_1ADD", 0x8b),
+    ("OP_1SUB", 0x8c),
+    ("OP_2MUL", 0x8d),
+    ("OP_2DIV", 0x8e),
+    ("OP_NEGATE", 0x8f),
+    ("OP_ABS", 0x90),
+    ("OP_NOT", 0x91),
+    ("OP_0NOTEQUAL", 0x92),
+    ("OP_ADD", 0x93),
+    ("OP_SUB", 0x94),
+    ("OP_MUL", 0x95),
+    ("OP_DIV", 0x96),
+    ("OP_MOD", 0x97),
+    ("OP_LSHIFT", 0x98),
+    ("OP_RSHIFT", 0x99),
+    ("OP_BOOLAND", 0x9a),
+    ("OP_BOOLOR", 0x9b),
+    ("OP_NUMEQUAL", 0x9c),
+    ("OP_NUMEQUALVERIFY", 0x9d),
+    ("OP_NUMNOTEQUAL", 0x9e),
+    ("OP_LESSTHAN", 0x9f),
+    ("OP_GREATERTHAN", 0xa0),
+    ("OP_LESSTHANOREQUAL", 0xa1),
+    ("OP_GREATERTHANOREQUAL", 0xa2),
+    ("OP_MIN", 0xa3),
+    ("OP_MAX", 0xa4),
+    ("OP_WITHIN", 0xa5),
+    ("OP_RIPEMD160", 0xa6),
+    ("OP_SHA1", 0xa7),
+    ("OP_SHA256", 0xa8),
+    ("OP_HASH160", 0xa9),
+    ("OP_HASH256", 0xaa),
+    ("OP_CODESEPARATOR", 0xab),
+    ("OP_CHECKSIG", 0xac),
+    ("OP_CHECKSIGVERIFY", 0xad),
+    ("OP_CHECKMULTISIG", 0xae),
+    ("OP_CHECKMULTISIGVERIFY", 0xaf),
+    ("OP_NOP1", 0xb0),
+    ("OP_CHECKLOCKTIMEVERIFY", 0xb1),
+    ("OP_CHECKSEQUENCEVERIFY", 0xb2),
+    ("OP_NOP4", 0xb3),
+    ("OP_NOP5", 0xb4),
+    ("OP_NOP6", 0xb5),
+    ("OP_NOP7", 0xb6),
+    ("OP_NOP8", 0xb7),
+    ("OP_NOP9", 0xb8),
+    ("OP_NOP10", 0xb9),
+    // Aliases
+    ("OP_FALSE", 0x00),
+    ("OP_TRUE", 0x51),
+    ("OP_NOP2", 0xb1),
+    ("OP_NOP3", 0xb2),
+];
+
+/// Prefix for opcodes without a name, followed by the decimal byte value.
+const UNKNOWN_PREFIX: &str = "OP_UNKNOWN";
+
+/// Canonical name of `op`, or `None` for direct push lengths (0x01-0x4b) and
+/// undefined opcodes (0xba-0xff).
+pub fn opcode_name(op: u8) -> Option<&'static str> {
+    OPCODES
+        .iter()
+        .find(|(_, o)| *o == op)
+        .map(|(name, _)| *name)
+}
+
+/// Opcode for a name or alias, with or without the `OP_` prefix.
+pub fn opcode_from_name(name: &str) -> Option<u8> {
+    let name = name.strip_prefix("OP_").unwrap_or(name);
+    OPCODES
+        .iter()
+        .find(|(n, _)| n[3..] == *name)
+        .map(|(_, op)| *op)
+}
+
+/// Disassembles a script into space-separated ASM tokens.
+///
+/// Opcodes print by name (`OP_UNKNOWN<n>` when undefined), direct pushes as
+/// `0x<hex>`, OP_PUSHDATA pushes as `OP_PUSHDATAn 0x<hex>` and a truncated
+/// trailing push as raw `[<hex>]`, so [`from_asm`] reproduces the script
+/// byte for byte.
+///
+/// ```
+/// use nprint_core::{from_asm, to_asm};
+///
+/// let script = [0x76, 0xa9, 0x02, 0xab, 0xcd, 0x88, 0xac];
+/// let asm = to_asm(&script);
+/// assert_eq!(asm, "OP_DUP OP_HASH160 0xabcd OP_EQUALVERIFY OP_CHECKSIG");
+/// assert_eq!(from_asm(&asm), Ok(script.to_vec()));
+/// ```
+pub fn to_asm(script: &[u8]) -> String {
+    let mut asm = String::new();
+    for (offset, instruction) in Instructions::new(script) {
+        if offset > 0 {
+            asm.push(' ');
+        }
+        write!(asm, "{instruction}").unwrap();
+    }
+    asm
+}
+
+/// Formats the instruction as its [`to_asm`] tokens.
+impl fmt::Display for Instruction<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = self.opcode();
+        match self {
+            Self::Push {
+                kind: PushKind::Direct,
+                data: [],
+            } => f.write_str("OP_0"),
+            Self::Push {
+                kind: PushKind::Direct,
+                data,
+            } => write_hex(f, "0x", data, ""),
+            Self::Push { data, .. } => {
+                f.write_str(opcode_name(op).unwrap())?;
+                write_hex(f, " 0x", data, "")
+            }
+            Self::Op(_) => match opcode_name(op) {
+                Some(name) => f.write_str(name),
+                None => write!(f, "{UNKNOWN_PREFIX}{op}"),
+            },
+            Self::Invalid(bytes) => write_hex(f, "[", bytes, "]"),
+        }
+    }
+}
+
+/// Assembles ASM text into a script.
+///
+/// Besides everything [`to_asm`] emits, accepts opcode aliases and names
+/// without the `OP_` prefix, decimal numbers (pushed minimally as script
+/// numbers) and `0x<hex>` data longer than a direct push, which gets the
+/// smallest OP_PUSHDATA encoding.
+///
+/// ```
+/// use nprint_core::from_asm;
+///
+/// assert_eq!(from_asm("2 -1 OP_ADD 1000"), Ok(vec![0x52, 0x4f, 0x93, 0x02, 0xe8, 0x03]));
+/// assert_eq!(from_asm("OP_PUSHDATA1 0x07"), Ok(vec![0x4c, 0x01, 0x07]));
+/// ```
+pub fn from_asm(asm: &str) -> Result<Vec<u8>, AsmError> {
+    let mut script = Vec::new();
+    let mut tokens = asm
+        .split_ascii_whitespace()
+        .map(|token| (token.as_ptr() as usize - asm.as_ptr() as usize, token));
+    while let Some((offset, token)) = tokens.next() {
+        let fail = |kind| AsmError { kind, offset };
+        if let Some(hex) = token.strip_prefix("0x") {
+            let data = decode_hex(hex).ok_or(fail(AsmErrorKind::InvalidHex))?;
+            script.extend(encode_push(&data, false));
+        } else if let Some(raw) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            script.extend(decode_hex(raw).ok_or(fail(AsmErrorKind::InvalidHex))?);
+        } else if let Some(op) = opcode_from_name(token) {
+            script.push(op);
+            if !(OP_PUSHDATA1..=OP_PUSHDATA4).contains(&op) {
+                continue;
+            }
+            let (data_offset, data) = tokens.next().ok_or(fail(AsmErrorKind::MissingPushData))?;
+            let data = data
+                .strip_prefix("0x")
+                .ok_or(fail(AsmErrorKind::MissingPushData))?;
+            let data = decode_hex(data).ok_or(AsmError {
+                kind: AsmErrorKind::InvalidHex,
+                offset: data_offset,
+            })?;
+            let len = data.len() as u64;
+            match op {
+                OP_PUSHDATA1 if len <= 0xff => script.push(len as u8),
+                OP_PUSHDATA2 if len <= 0xffff => script.extend((len as u16).to_le_bytes()),
+                OP_PUSHDATA4 if len <= 0xffff_ffff => script.extend((len as u32).to_le_bytes()),
+                _ => return Err(fail(AsmErrorKind::PushTooLong)),
+            }
+            script.extend(data);
+        } else if let Some(op) = token
+            .strip_prefix(UNKNOWN_PREFIX)
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|op| opcode_name(*op).is_none() && *op > OP_PUSHDATA4)
+        {
+            script.push(op);
+        } else if is_decimal(token) {
+            let n =
+                BigInt::from_str_radix(token, 10).map_err(|_| fail(AsmErrorKind::UnknownToken))?;
+            script.extend(ScriptNum::from(n).to_push());
+        } else {
+            return Err(fail(AsmErrorKind::UnknownToken));
+        }
+    }
+    Ok(script)
+}
+
+fn is_decimal(token: &str) -> bool {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn write_hex(out: &mut impl Write, prefix: &str, bytes: &[u8], suffix: &str) -> fmt::Result {
+    out.write_str(prefix)?;
+    for b in bytes {
+        write!(out, "{b:02x}")?;
+    }
+    out.write_str(suffix)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,144 @@
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use k256::ecdsa::{Signature, VerifyingKey};
+
+use crate::transaction::{
+    SIGHASH_ANYONECANPAY, SIGHASH_FORKID, SIGHASH_SINGLE, Transaction, TxInput,
+};
+use crate::{ScriptError, ScriptErrorKind, ScriptFlags, Stack, decode_bool};
+
+/// Lock times below this are block heights, at or above it Unix timestamps.
+const LOCKTIME_THRESHOLD: i64 = 500_000_000;
+/// Sequence bit disabling relative lock time.
+const SEQUENCE_DISABLE_FLAG: i64 = 1 << 31;
+/// Sequence bit selecting 512-second units rather than blocks.
+const SEQUENCE_TYPE_FLAG: i64 = 1 << 22;
+/// Sequence bits holding the relative lock time value.
+const SEQUENCE_MASK: i64 = 0xffff;
+
+/// Transaction being validated, for the signature opcodes.
+///
+/// ```
+/// use nprint_core::{ExecutionContext, ScriptFlags, Transaction, TxInput};
+///
+/// let tx = Transaction {
+///     inputs: vec![TxInput { script_sig: vec![0x51], ..Default::default() }],
+///     ..Default::default()
+/// };
+/// let ctx = ExecutionContext {
+///     tx: &tx,
+///     input_index: 0,
+///     amount: 1000,
+///     locking_script: &[],
+///     flags: ScriptFlags::GENESIS_POLICY,
+/// };
+/// assert_eq!(ctx.verify(), Ok(true));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct ExecutionContext<'a> {
+    /// Spending transaction.
+    pub tx: &'a Transaction,
+    /// Index of the input being validated.
+    pub input_index: usize,
+    /// Value in satoshis of the output being spent.
+    pub amount: u64,
+    /// Locking script of the output being spent.
+    pub locking_script: &'a [u8],
+    /// Rule set `verify` runs the scripts under.
+    pub flags: ScriptFlags,
+}
+
+impl ExecutionContext<'_> {
+    /// Runs the input's unlocking script, then the locking script, and
+    /// reports whether the spend succeeds.
+    ///
+    /// An `input_index` the transaction does not have fails with
+    /// [`ScriptErrorKind::InvalidInputIndex`] at offset 0.
+    pub fn verify(&self) -> Result<bool, ScriptError> {
+        let input = self.input().map_err(|kind| ScriptError {
+            kind,
+            opcode: 0,
+            offset: 0,
+        })?;
+        let mut stack = Stack {
+            flags: self.flags,
+            ..Default::default()
+        };
+        stack.execute_with(&input.script_sig, self)?;
+        stack.alt.clear();
+        stack.execute_with(self.locking_script, self)?;
+        Ok(stack.main.last().is_some_and(|top| decode_bool(top)))
+    }
+
+    /// The input being validated.
+    pub(crate) fn input(&self) -> Result<&TxInput, ScriptErrorKind> {
+        self.tx
+            .inputs
+            .get(self.input_index)
+            .ok_or(ScriptErrorKind::InvalidInputIndex)
+    }
+
+    /// Checks `sig` (DER plus sighash type byte) against `pubkey` over the
+    /// signature hash of `script_code`.
+    ///
+    /// An empty signature is a failed check; malformed signatures and keys
+    /// are errors. High-S signatures are accepted.
+    pub(crate) fn check_sig(
+        &self,
+        sig: &[u8],
+        pubkey: &[u8],
+        script_code: &[u8],
+    ) -> Result<bool, ScriptErrorKind> {
+        let Some((&sighash_type, der)) = sig.split_last() else {
+            return Ok(false);
+        };
+        let signature = Signature::from_der(der).map_err(|_| ScriptErrorKind::SignatureEncoding)?;
+        let base = sighash_type & !(SIGHASH_ANYONECANPAY | SIGHASH_FORKID);
+        if base == 0 || base > SIGHASH_SINGLE {
+            return Err(ScriptErrorKind::SignatureEncoding);
+        }
+        if sighash_type & SIGHASH_FORKID == 0 {
+            return Err(ScriptErrorKind::MissingForkId);
+        }
+        let key =
+            VerifyingKey::from_sec1_bytes(pubkey).map_err(|_| ScriptErrorKind::PubKeyEncoding)?;
+        let signature = signature.normalize_s().unwrap_or(signature);
+        self.input()?;
+        let hash = self
+            .tx
+            .sighash(self.input_index, script_code, self.amount, sighash_type);
+        Ok(key.verify_prehash(&hash, &signature).is_ok())
+    }
+
+    /// BIP65: whether the transaction's lock time satisfies `lock_time`.
+    pub(crate) fn check_lock_time(&self, lock_time: i64) -> Result<bool, ScriptErrorKind> {
+        let input = self.input()?;
+        let tx_lock_time = self.tx.lock_time as i64;
+        if (tx_lock_time < LOCKTIME_THRESHOLD) != (lock_time < LOCKTIME_THRESHOLD) {
+            return Ok(false);
+        }
+        if lock_time > tx_lock_time {
+            return Ok(false);
+        }
+        // A final input would let the lock time be bypassed
+        Ok(input.sequence != u32::MAX)
+    }
+
+    /// BIP112: whether the input's sequence satisfies the relative lock `sequence`.
+    pub(crate) fn check_sequence(&self, sequence: i64) -> Result<bool, ScriptErrorKind> {
+        let input = self.input()?;
+        // Consensus reads the version as a signed 32-bit integer
+        if (self.tx.version as i32) < 2 {
+            return Ok(false);
+        }
+        let tx_sequence = input.sequence as i64;
+        if tx_sequence & SEQUENCE_DISABLE_FLAG != 0 {
+            return Ok(false);
+        }
+        let mask = SEQUENCE_TYPE_FLAG | SEQUENCE_MASK;
+        let (required, actual) = (sequence & mask, tx_sequence & mask);
+        if (required < SEQUENCE_TYPE_FLAG) != (actual < SEQUENCE_TYPE_FLAG) {
+            return Ok(false);
+        }
+        Ok(required <= actual)
+    }
+}
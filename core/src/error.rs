@@ -0,0 +1,277 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Reason a script failed to execute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ScriptErrorKind {
+    /// The main stack holds fewer items than the opcode consumes, or an
+    /// OP_PICK/OP_ROLL index lies outside it.
+    StackUnderflow,
+    /// OP_FROMALTSTACK on an empty alt stack.
+    AltStackUnderflow,
+    /// The opcode is not known to the interpreter.
+    InvalidOpcode,
+    /// The opcode is disabled under the active rules.
+    DisabledOpcode,
+    /// A push or an opcode result exceeds the maximum element size.
+    PushSizeExceeded,
+    /// A push runs past the end of the script.
+    TruncatedPush,
+    /// A numeric operand is longer than the maximum number size, or a result overflows.
+    NumberOverflow,
+    /// A numeric operand carries superfluous zero padding.
+    NonMinimalNumber,
+    /// A data push does not use the shortest encoding of its data.
+    NonMinimalPush,
+    /// OP_DIV or OP_MOD with a zero divisor.
+    DivisionByZero,
+    /// A numeric argument (split point, shift, count, size) is out of range.
+    InvalidNumberRange,
+    /// OP_NUM2BIN cannot fit the number in the requested size.
+    ImpossibleEncoding,
+    /// Bitwise operands differ in length.
+    OperandSizeMismatch,
+    /// OP_VERIFY or a *VERIFY opcode found a false value.
+    VerifyFailed,
+    /// OP_ELSE/OP_ENDIF without OP_IF, or OP_IF without OP_ENDIF.
+    UnbalancedConditional,
+    /// A signature or lock time opcode was executed without a transaction context.
+    MissingContext,
+    /// The transaction context names an input the transaction does not have.
+    InvalidInputIndex,
+    /// A signature is not strict DER or has an undefined sighash type.
+    SignatureEncoding,
+    /// A signature lacks SIGHASH_FORKID.
+    MissingForkId,
+    /// A public key is not a valid SEC1 point.
+    PubKeyEncoding,
+    /// OP_CHECKLOCKTIMEVERIFY or OP_CHECKSEQUENCEVERIFY with a negative lock time.
+    NegativeLocktime,
+    /// The transaction does not satisfy the lock time required by the script.
+    UnsatisfiedLocktime,
+    /// OP_RETURN executed under pre-Genesis rules.
+    EarlyReturn,
+    /// The script is longer than the maximum script size.
+    ScriptSizeExceeded,
+    /// The script has more non-push opcodes than allowed.
+    OpCountExceeded,
+    /// The stacks hold more elements or memory than allowed.
+    StackSizeExceeded,
+}
+
+impl fmt::Display for ScriptErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::StackUnderflow => "Stack underflow",
+            Self::AltStackUnderflow => "Alt stack underflow",
+            Self::InvalidOpcode => "Invalid opcode",
+            Self::DisabledOpcode => "Disabled opcode",
+            Self::PushSizeExceeded => "Push size exceeded",
+            Self::TruncatedPush => "Push data exceeds script length",
+            Self::NumberOverflow => "Number overflow",
+            Self::NonMinimalNumber => "Non-minimally encoded number",
+            Self::NonMinimalPush => "Non-minimal data push",
+            Self::DivisionByZero => "Division by zero",
+            Self::InvalidNumberRange => "Number out of range",
+            Self::ImpossibleEncoding => "Number does not fit requested size",
+            Self::OperandSizeMismatch => "Operands differ in length",
+            Self::VerifyFailed => "Verify failed",
+            Self::UnbalancedConditional => "Unbalanced conditional",
+            Self::MissingContext => "Opcode requires a transaction context",
+            Self::InvalidInputIndex => "Input index out of range",
+            Self::SignatureEncoding => "Invalid signature encoding",
+            Self::MissingForkId => "Signature must use SIGHASH_FORKID",
+            Self::PubKeyEncoding => "Invalid public key encoding",
+            Self::NegativeLocktime => "Negative lock time",
+            Self::UnsatisfiedLocktime => "Lock time requirement not satisfied",
+            Self::EarlyReturn => "OP_RETURN executed",
+            Self::ScriptSizeExceeded => "Script size exceeded",
+            Self::OpCountExceeded => "Opcode count exceeded",
+            Self::StackSizeExceeded => "Stack size exceeded",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Script failure with the opcode and byte offset at which it occurred.
+///
+/// A conditional left open at the end of the script is reported as a
+/// missing OP_ENDIF at offset `script.len()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScriptError {
+    pub kind: ScriptErrorKind,
+    pub opcode: u8,
+    pub offset: usize,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (opcode 0x{:02x} at offset {})",
+            self.kind, self.opcode, self.offset
+        )
+    }
+}
+
+impl core::error::Error for ScriptError {}
+
+/// Reason ASM text failed to assemble.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AsmErrorKind {
+    /// Not an opcode name, number, hex push or raw bytes.
+    UnknownToken,
+    /// Hex data with an odd length or a non-hex digit.
+    InvalidHex,
+    /// OP_PUSHDATA1/2/4 not followed by `0x<hex>` data.
+    MissingPushData,
+    /// Data too long for the requested OP_PUSHDATA length field.
+    PushTooLong,
+}
+
+impl fmt::Display for AsmErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::UnknownToken => "Unknown token",
+            Self::InvalidHex => "Invalid hex data",
+            Self::MissingPushData => "Missing push data",
+            Self::PushTooLong => "Push data too long for its length field",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// ASM failure with the byte offset of the offending token in the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AsmError {
+    pub kind: AsmErrorKind,
+    pub offset: usize,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.kind, self.offset)
+    }
+}
+
+impl core::error::Error for AsmError {}
+
+/// Reason a macro failed to expand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MacroErrorKind {
+    /// The number of arguments differs from the macro's `param_count`.
+    ArgCountMismatch,
+    /// A parameter index is not below the macro's `param_count`.
+    ParamOutOfRange,
+    /// A referenced macro is not in the registry.
+    UnknownMacro,
+    /// A macro references itself, directly or through other macros.
+    Cycle,
+    /// Macro references are nested deeper than the registry's limit.
+    RecursionLimit,
+    /// A parameter expression overflows `i32`.
+    ArithmeticOverflow,
+    /// A repeat count exceeds [`crate::MAX_UNROLL`].
+    RepeatLimit,
+}
+
+impl fmt::Display for MacroErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::ArgCountMismatch => "Wrong number of arguments",
+            Self::ParamOutOfRange => "Parameter index out of range",
+            Self::UnknownMacro => "Unknown macro",
+            Self::Cycle => "Recursive macro reference",
+            Self::RecursionLimit => "Macro nesting too deep",
+            Self::ArithmeticOverflow => "Parameter arithmetic overflow",
+            Self::RepeatLimit => "Repeat count too large",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// Macro expansion failure with the name of the macro at fault: the missing
+/// one for [`MacroErrorKind::UnknownMacro`], otherwise the one being expanded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MacroError {
+    pub kind: MacroErrorKind,
+    pub name: String,
+}
+
+impl fmt::Display for MacroError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: `{}`", self.kind, self.name)
+    }
+}
+
+impl core::error::Error for MacroError {}
+
+/// Reason `.nmacro` text failed to parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MacroParseErrorKind {
+    /// A token that does not fit the grammar at this point.
+    UnexpectedToken,
+    /// The text ends inside a definition.
+    UnexpectedEnd,
+    /// A body word that is not an opcode name, number or macro call.
+    UnknownOpcode,
+    /// `$name` where `name` is not a parameter of the macro.
+    UnknownParam,
+    /// A parameter name listed twice.
+    DuplicateParam,
+    /// A macro defined twice in the same text.
+    DuplicateMacro,
+    /// A number outside the `i32` range.
+    InvalidNumber,
+}
+
+impl fmt::Display for MacroParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::UnexpectedToken => "Unexpected token",
+            Self::UnexpectedEnd => "Unexpected end of input",
+            Self::UnknownOpcode => "Unknown opcode",
+            Self::UnknownParam => "Unknown parameter",
+            Self::DuplicateParam => "Duplicate parameter",
+            Self::DuplicateMacro => "Duplicate macro",
+            Self::InvalidNumber => "Number out of range",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// `.nmacro` failure with the 1-based line and column of the offending token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MacroParseError {
+    pub kind: MacroParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for MacroParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.kind, self.line, self.column
+        )
+    }
+}
+
+impl core::error::Error for MacroParseError {}
+
+/// Input stack, bottom first, on which two scripts checked by
+/// [`check_equivalence`](crate::check_equivalence) disagree.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EquivalenceError {
+    pub inputs: Vec<Vec<u8>>,
+}
+
+impl fmt::Display for EquivalenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Scripts disagree on input stack {:02x?}", self.inputs)
+    }
+}
+
+impl core::error::Error for EquivalenceError {}
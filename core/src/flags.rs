@@ -0,0 +1,88 @@
+use sv::script::op_codes::{OP_2DIV, OP_2MUL, OP_INVERT, OP_LSHIFT, OP_MUL, OP_RSHIFT};
+
+use crate::{MAX_ELEMENT_SIZE, MAX_NUM_SIZE};
+
+/// Stack memory charged per element on top of its length.
+pub const ELEMENT_OVERHEAD: usize = 32;
+
+/// Rule set and resource limits for script execution.
+///
+/// Start from one of the presets and override individual limits:
+///
+/// ```
+/// use nprint_core::{ScriptFlags, Stack};
+///
+/// let flags = ScriptFlags { max_ops: 10, ..ScriptFlags::GENESIS_POLICY };
+/// let mut stack = Stack { flags, ..Default::default() };
+/// assert!(stack.execute(&[0x61; 11]).is_err()); // 11 x OP_NOP
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScriptFlags {
+    /// Post-Genesis semantics: an executed OP_RETURN ends the script
+    /// successfully and OP_CHECKLOCKTIMEVERIFY/OP_CHECKSEQUENCEVERIFY are NOPs.
+    pub genesis: bool,
+    /// Maximum script length in bytes.
+    pub max_script_size: usize,
+    /// Maximum number of non-push opcodes per script, executed or not.
+    pub max_ops: usize,
+    /// Maximum length of a pushed or computed stack element.
+    pub max_element_size: usize,
+    /// Maximum byte length of a numeric operand.
+    pub max_num_size: usize,
+    /// Maximum combined number of main and alt stack elements.
+    pub max_stack_items: usize,
+    /// Maximum combined main and alt stack memory, counting each element's
+    /// length plus [`ELEMENT_OVERHEAD`].
+    pub max_stack_memory: usize,
+    /// Opcodes that fail the script wherever they appear, even in
+    /// unexecuted branches.
+    pub disabled_opcodes: &'static [u8],
+    /// MINIMALDATA: executed pushes must use the shortest encoding of their
+    /// data (see [`encode_push`](crate::encode_push)) and numeric operands
+    /// must carry no zero padding. A policy rule, not consensus.
+    pub require_minimal: bool,
+}
+
+impl ScriptFlags {
+    /// Consensus rules for outputs created before the Genesis upgrade.
+    pub const PRE_GENESIS: Self = Self {
+        genesis: false,
+        max_script_size: 10_000,
+        max_ops: 500,
+        max_element_size: MAX_ELEMENT_SIZE,
+        max_num_size: MAX_NUM_SIZE,
+        max_stack_items: 1_000,
+        max_stack_memory: usize::MAX,
+        // OP_MUL, OP_LSHIFT, OP_RSHIFT and OP_INVERT were only re-enabled at Genesis
+        disabled_opcodes: &[OP_2MUL, OP_2DIV, OP_MUL, OP_LSHIFT, OP_RSHIFT, OP_INVERT],
+        require_minimal: false,
+    };
+
+    /// Consensus rules after the Genesis upgrade.
+    pub const GENESIS_CONSENSUS: Self = Self {
+        genesis: true,
+        max_script_size: u32::MAX as usize,
+        max_ops: usize::MAX,
+        max_element_size: usize::MAX,
+        max_num_size: 750_000,
+        max_stack_items: usize::MAX,
+        max_stack_memory: usize::MAX,
+        disabled_opcodes: &[OP_2MUL, OP_2DIV],
+        require_minimal: false,
+    };
+
+    /// Default miner policy after the Genesis upgrade; what a node relays.
+    pub const GENESIS_POLICY: Self = Self {
+        max_script_size: 500_000,
+        max_num_size: 250_000,
+        max_stack_memory: 100_000_000,
+        require_minimal: true,
+        ..Self::GENESIS_CONSENSUS
+    };
+}
+
+impl Default for ScriptFlags {
+    fn default() -> Self {
+        Self::GENESIS_POLICY
+    }
+}
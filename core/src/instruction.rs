@@ -0,0 +1,198 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use nom::IResult;
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u8, le_u16, le_u32};
+use sv::script::op_codes::{OP_0, OP_1, OP_1NEGATE, OP_PUSHDATA1, OP_PUSHDATA2, OP_PUSHDATA4};
+
+/// How a data push encodes its length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PushKind {
+    /// OP_0 or opcodes 0x01-0x4b; the opcode is the length.
+    Direct,
+    /// OP_PUSHDATA1 with a one-byte length.
+    PushData1,
+    /// OP_PUSHDATA2 with a two-byte little-endian length.
+    PushData2,
+    /// OP_PUSHDATA4 with a four-byte little-endian length.
+    PushData4,
+}
+
+/// A single script instruction, borrowing its payload from the script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Instruction<'a> {
+    /// Any opcode above OP_PUSHDATA4, including OP_1 to OP_16 and undefined ones.
+    Op(u8),
+    /// Data push.
+    Push { kind: PushKind, data: &'a [u8] },
+    /// Push running past the end of the script: the opcode and every byte after it.
+    Invalid(&'a [u8]),
+}
+
+impl Instruction<'_> {
+    /// The instruction's opcode byte.
+    pub fn opcode(&self) -> u8 {
+        match self {
+            Self::Op(op) => *op,
+            Self::Push { kind, data } => match kind {
+                PushKind::Direct => data.len() as u8,
+                PushKind::PushData1 => OP_PUSHDATA1,
+                PushKind::PushData2 => OP_PUSHDATA2,
+                PushKind::PushData4 => OP_PUSHDATA4,
+            },
+            Self::Invalid(bytes) => bytes[0],
+        }
+    }
+
+    /// Number of script bytes the instruction occupies.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Op(_) => 1,
+            Self::Push { kind, data } => {
+                let width = match kind {
+                    PushKind::Direct => 0,
+                    PushKind::PushData1 => 1,
+                    PushKind::PushData2 => 2,
+                    PushKind::PushData4 => 4,
+                };
+                1 + width + data.len()
+            }
+            Self::Invalid(bytes) => bytes.len(),
+        }
+    }
+
+    /// Always false; every instruction occupies at least its opcode byte.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Whether a push of `data` encoded as `kind` is the one [`encode_push`]
+/// produces in minimal mode; single bytes with a number opcode never are.
+pub(crate) fn is_minimal_push(kind: PushKind, data: &[u8]) -> bool {
+    let shortest = match data {
+        [1..=16] | [0x81] => return false,
+        _ if data.len() < OP_PUSHDATA1 as usize => PushKind::Direct,
+        _ if data.len() <= 0xff => PushKind::PushData1,
+        _ if data.len() <= 0xffff => PushKind::PushData2,
+        _ => PushKind::PushData4,
+    };
+    kind == shortest
+}
+
+/// Parses one instruction from the front of a non-empty `input`.
+fn instruction(input: &[u8]) -> IResult<&[u8], Instruction<'_>> {
+    let (rest, op) = le_u8(input)?;
+    let (rest, kind, len) = match op {
+        OP_PUSHDATA1 => {
+            let (rest, len) = le_u8(rest)?;
+            (rest, PushKind::PushData1, len as usize)
+        }
+        OP_PUSHDATA2 => {
+            let (rest, len) = le_u16(rest)?;
+            (rest, PushKind::PushData2, len as usize)
+        }
+        OP_PUSHDATA4 => {
+            let (rest, len) = le_u32(rest)?;
+            (rest, PushKind::PushData4, len as usize)
+        }
+        len if len < OP_PUSHDATA1 => (rest, PushKind::Direct, len as usize),
+        op => return Ok((rest, Instruction::Op(op))),
+    };
+    let (rest, data) = take(len)(rest)?;
+    Ok((rest, Instruction::Push { kind, data }))
+}
+
+/// Script fragment pushing `data`: OP_0 when empty, a direct push for up to
+/// 75 bytes, else the narrowest of OP_PUSHDATA1, OP_PUSHDATA2 and
+/// OP_PUSHDATA4.
+///
+/// With `minimal` set the result also satisfies the minimal-push rule: a
+/// single byte 1 to 16 becomes OP_1 to OP_16 and 0x81 becomes OP_1NEGATE.
+/// Without it the data bytes always follow the length, so the push
+/// round-trips through [`to_asm`](crate::to_asm) as hex.
+///
+/// ```
+/// use nprint_core::encode_push;
+///
+/// assert_eq!(encode_push(&[0x05], false), [0x01, 0x05]);
+/// assert_eq!(encode_push(&[0x05], true), [0x55]);
+/// assert_eq!(encode_push(&[0xab; 80], true)[..2], [0x4c, 80]);
+/// assert_eq!(encode_push(&[0xab; 300], true)[..3], [0x4d, 0x2c, 0x01]);
+/// ```
+pub fn encode_push(data: &[u8], minimal: bool) -> Vec<u8> {
+    if minimal {
+        match data {
+            [n @ 1..=16] => return vec![OP_1 + (n - 1)],
+            [0x81] => return vec![OP_1NEGATE],
+            _ => {}
+        }
+    }
+    let mut script = Vec::with_capacity(data.len() + 5);
+    match data.len() {
+        0 => script.push(OP_0),
+        len if len < OP_PUSHDATA1 as usize => script.push(len as u8),
+        len if len <= 0xff => script.extend([OP_PUSHDATA1, len as u8]),
+        len if len <= 0xffff => {
+            script.push(OP_PUSHDATA2);
+            script.extend((len as u16).to_le_bytes());
+        }
+        len => {
+            script.push(OP_PUSHDATA4);
+            script.extend((len as u32).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+    script
+}
+
+/// Iterator over a script's instructions and their byte offsets.
+///
+/// A truncated push yields [`Instruction::Invalid`] and ends the iteration.
+#[derive(Clone, Debug)]
+pub struct Instructions<'a> {
+    script: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Instructions<'a> {
+    pub fn new(script: &'a [u8]) -> Self {
+        Self { script, offset: 0 }
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = (usize, Instruction<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.script.get(self.offset..).filter(|r| !r.is_empty())?;
+        let offset = self.offset;
+        let instruction = match instruction(rest) {
+            Ok((_, instruction)) => instruction,
+            Err(_) => Instruction::Invalid(rest),
+        };
+        self.offset += instruction.len();
+        Some((offset, instruction))
+    }
+}
+
+/// Tokenizes a script into instructions without copying push data.
+///
+/// The whole input is consumed; a truncated push at the end becomes
+/// [`Instruction::Invalid`].
+///
+/// ```
+/// use nprint_core::{Instruction, PushKind, parse_script};
+///
+/// let (rest, instructions) = parse_script(&[0x02, 0xab, 0xcd, 0x76, 0x4c, 0x05]).unwrap();
+/// assert!(rest.is_empty());
+/// assert_eq!(instructions, vec![
+///     Instruction::Push { kind: PushKind::Direct, data: &[0xab, 0xcd] },
+///     Instruction::Op(0x76),
+///     Instruction::Invalid(&[0x4c, 0x05]),
+/// ]);
+/// ```
+pub fn parse_script(input: &[u8]) -> IResult<&[u8], Vec<Instruction<'_>>> {
+    let instructions = Instructions::new(input).map(|(_, i)| i).collect();
+    Ok((&input[input.len()..], instructions))
+}
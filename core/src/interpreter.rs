@@ -0,0 +1,380 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use sv::script::op_codes::{
+    OP_16, OP_CHECKLOCKTIMEVERIFY, OP_CHECKMULTISIGVERIFY, OP_CHECKSEQUENCEVERIFY, OP_CHECKSIG,
+    OP_CODESEPARATOR, OP_ENDIF, OP_RETURN,
+};
+
+use crate::instruction::is_minimal_push;
+use crate::{ExecutionContext, Instruction, Instructions, ScriptError, ScriptErrorKind, Stack};
+
+/// Where [`Interpreter::resume`] pauses: before the instruction at a byte
+/// offset, or before any instruction with the given opcode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Breakpoint {
+    Offset(usize),
+    Opcode(u8),
+}
+
+/// Why [`Interpreter::resume`] returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Halt {
+    /// The next instruction, at `offset`, matches a breakpoint.
+    Breakpoint { offset: usize },
+    /// The script ran to completion.
+    Finished,
+}
+
+/// One instruction processed by [`Interpreter::step`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Step<'a> {
+    pub offset: usize,
+    pub instruction: Instruction<'a>,
+    /// False when the instruction sat in an unexecuted branch.
+    pub executed: bool,
+}
+
+/// Copy of the main, alt and condition stacks.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    pub main: Vec<Vec<u8>>,
+    pub alt: Vec<Vec<u8>>,
+    pub cond: Vec<bool>,
+}
+
+/// A step together with the stacks after it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TraceStep<'a> {
+    pub step: Step<'a>,
+    pub snapshot: Snapshot,
+}
+
+/// Every step of an execution, and the error that ended it, if any.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Trace<'a> {
+    pub steps: Vec<TraceStep<'a>>,
+    pub error: Option<ScriptError>,
+}
+
+/// Executes a script one instruction at a time against a [`Stack`].
+///
+/// [`Stack::execute`] and [`Stack::execute_with`] run an interpreter to
+/// completion; use one directly to inspect the stacks between instructions.
+///
+/// ```
+/// use nprint_core::{Breakpoint, Halt, Interpreter, Stack};
+///
+/// let mut stack = Stack::default();
+/// let script = [0x51, 0x52, 0x93, 0x76]; // OP_1 OP_2 OP_ADD OP_DUP
+/// let mut interpreter = Interpreter::new(&mut stack, &script);
+/// interpreter.add_breakpoint(Breakpoint::Opcode(0x76));
+/// assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 3 }));
+/// assert_eq!(interpreter.snapshot().main, vec![vec![3]]);
+/// let step = interpreter.step().unwrap().unwrap();
+/// assert_eq!(step.offset, 3);
+/// assert_eq!(interpreter.resume(), Ok(Halt::Finished));
+/// assert_eq!(stack.main, vec![vec![3], vec![3]]);
+/// ```
+pub struct Interpreter<'a> {
+    stack: &'a mut Stack,
+    script: &'a [u8],
+    ctx: Option<&'a ExecutionContext<'a>>,
+    instructions: Instructions<'a>,
+    breakpoints: Vec<Breakpoint>,
+    started: bool,
+    // Whether `resume` has checked the first instruction for a breakpoint
+    entered: bool,
+    finished: bool,
+    returned: bool,
+    ops: usize,
+    // Signatures cover the script after the last executed OP_CODESEPARATOR
+    code_start: usize,
+}
+
+impl<'a> Interpreter<'a> {
+    /// Prepares `script` for execution under `stack.flags`, clearing the
+    /// condition stack and recounting the stack memory. Signature and lock
+    /// time opcodes need [`Interpreter::with_context`].
+    pub fn new(stack: &'a mut Stack, script: &'a [u8]) -> Self {
+        stack.cond.clear();
+        stack.recount_memory();
+        Self {
+            stack,
+            script,
+            ctx: None,
+            instructions: Instructions::new(script),
+            breakpoints: Vec::new(),
+            started: false,
+            entered: false,
+            finished: false,
+            returned: false,
+            ops: 0,
+            code_start: 0,
+        }
+    }
+
+    /// Executes as part of the spend described by `ctx`.
+    pub fn with_context(mut self, ctx: &'a ExecutionContext<'a>) -> Self {
+        self.ctx = Some(ctx);
+        self
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.retain(|b| *b != breakpoint);
+    }
+
+    /// The stacks as they stand.
+    pub fn stack(&self) -> &Stack {
+        self.stack
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            main: self.stack.main.clone(),
+            alt: self.stack.alt.clone(),
+            cond: self.stack.cond.clone(),
+        }
+    }
+
+    /// Offset and instruction to be executed next, if any.
+    pub fn next_instruction(&self) -> Option<(usize, Instruction<'a>)> {
+        if self.finished {
+            return None;
+        }
+        self.instructions.clone().next()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Executes the next instruction.
+    ///
+    /// Returns `Ok(None)` once the script has finished. After an error the
+    /// interpreter is finished and the stacks are left as the failing
+    /// instruction found them.
+    pub fn step(&mut self) -> Result<Option<Step<'a>>, ScriptError> {
+        if self.finished {
+            return Ok(None);
+        }
+        let result = self.execute_next();
+        if !matches!(result, Ok(Some(_))) {
+            self.finished = true;
+        }
+        result
+    }
+
+    /// Steps until the next instruction matches a breakpoint or the script
+    /// finishes. The first call halts before the first instruction if it
+    /// matches; otherwise at least one instruction is executed, so resuming
+    /// from a breakpoint moves past it.
+    pub fn resume(&mut self) -> Result<Halt, ScriptError> {
+        if !self.entered && !self.started {
+            self.entered = true;
+            if let Some(offset) = self.breakpoint_hit() {
+                return Ok(Halt::Breakpoint { offset });
+            }
+        }
+        while self.step()?.is_some() {
+            if let Some(offset) = self.breakpoint_hit() {
+                return Ok(Halt::Breakpoint { offset });
+            }
+        }
+        Ok(Halt::Finished)
+    }
+
+    /// Offset of the next instruction if it matches a breakpoint.
+    fn breakpoint_hit(&self) -> Option<usize> {
+        let (offset, instruction) = self.next_instruction()?;
+        self.breakpoints
+            .iter()
+            .any(|b| match b {
+                Breakpoint::Offset(o) => *o == offset,
+                Breakpoint::Opcode(op) => *op == instruction.opcode(),
+            })
+            .then_some(offset)
+    }
+
+    /// Runs to completion, ignoring breakpoints.
+    pub(crate) fn run(mut self) -> Result<(), ScriptError> {
+        while self.step()?.is_some() {}
+        Ok(())
+    }
+
+    /// Runs to completion, ignoring breakpoints, recording a snapshot after
+    /// every instruction.
+    pub fn trace(&mut self) -> Trace<'a> {
+        let mut trace = Trace::default();
+        loop {
+            match self.step() {
+                Ok(Some(step)) => trace.steps.push(TraceStep {
+                    step,
+                    snapshot: self.snapshot(),
+                }),
+                Ok(None) => break,
+                Err(err) => {
+                    trace.error = Some(err);
+                    break;
+                }
+            }
+        }
+        trace
+    }
+
+    fn execute_next(&mut self) -> Result<Option<Step<'a>>, ScriptError> {
+        let flags = self.stack.flags;
+        if !self.started {
+            self.started = true;
+            if self.script.len() > flags.max_script_size {
+                return Err(ScriptError {
+                    kind: ScriptErrorKind::ScriptSizeExceeded,
+                    opcode: self.script[flags.max_script_size],
+                    offset: flags.max_script_size,
+                });
+            }
+            if let Some(kind) = self.ctx.and_then(|ctx| ctx.input().err()) {
+                return Err(ScriptError {
+                    kind,
+                    opcode: self.script.first().copied().unwrap_or(0),
+                    offset: 0,
+                });
+            }
+        }
+        let Some((offset, instruction)) = self.instructions.next() else {
+            if !self.stack.cond.is_empty() {
+                return Err(ScriptError {
+                    kind: ScriptErrorKind::UnbalancedConditional,
+                    opcode: OP_ENDIF,
+                    offset: self.script.len(),
+                });
+            }
+            return Ok(None);
+        };
+        let op = instruction.opcode();
+        let fail = |kind| ScriptError {
+            kind,
+            opcode: op,
+            offset,
+        };
+        let executing = !self.returned && self.stack.cond.iter().all(|c| *c);
+        let step = Step {
+            offset,
+            instruction,
+            executed: executing,
+        };
+        if op > OP_16 {
+            self.ops += 1;
+            if self.ops > flags.max_ops {
+                return Err(fail(ScriptErrorKind::OpCountExceeded));
+            }
+        }
+        if flags.disabled_opcodes.contains(&op) {
+            return Err(fail(ScriptErrorKind::DisabledOpcode));
+        }
+        match instruction {
+            Instruction::Invalid(_) => return Err(fail(ScriptErrorKind::TruncatedPush)),
+            Instruction::Push { kind, data } => {
+                if data.len() > flags.max_element_size {
+                    return Err(fail(ScriptErrorKind::PushSizeExceeded));
+                }
+                if executing && flags.require_minimal && !is_minimal_push(kind, data) {
+                    return Err(fail(ScriptErrorKind::NonMinimalPush));
+                }
+                if executing {
+                    self.stack.push(data.to_vec());
+                }
+            }
+            Instruction::Op(OP_RETURN) if executing => {
+                if !flags.genesis {
+                    return Err(fail(ScriptErrorKind::EarlyReturn));
+                }
+                if self.stack.cond.is_empty() {
+                    // Nothing after a top-level OP_RETURN is looked at
+                    self.finished = true;
+                    return Ok(Some(step));
+                }
+                // Inside a branch the rest of the script is only checked for balance
+                self.returned = true;
+            }
+            Instruction::Op(OP_CODESEPARATOR) if executing => self.code_start = offset + 1,
+            Instruction::Op(OP_CHECKSIG..=OP_CHECKMULTISIGVERIFY) if executing => {
+                let ctx = self.ctx.ok_or(fail(ScriptErrorKind::MissingContext))?;
+                self.stack
+                    .execute_checksig(op, ctx, &self.script[self.code_start..])
+                    .map_err(fail)?;
+            }
+            Instruction::Op(OP_CHECKLOCKTIMEVERIFY | OP_CHECKSEQUENCEVERIFY)
+                if executing && !flags.genesis =>
+            {
+                let ctx = self.ctx.ok_or(fail(ScriptErrorKind::MissingContext))?;
+                self.stack.execute_locktime(op, ctx).map_err(fail)?;
+            }
+            Instruction::Op(_) => self.stack.execute_op(op, executing).map_err(fail)?,
+        }
+        self.stack.check_stack_size().map_err(fail)?;
+        Ok(Some(step))
+    }
+}
+
+impl Trace<'_> {
+    /// Renders the trace as JSON: a `steps` array holding each instruction's
+    /// offset, ASM, whether it executed and the hex-encoded stacks after it,
+    /// and an `error` object (kind, opcode, offset) or null.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{\"steps\":[");
+        for (i, TraceStep { step, snapshot }) in self.steps.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            write!(
+                json,
+                "{{\"offset\":{},\"asm\":\"{}\",\"executed\":{},\"main\":",
+                step.offset, step.instruction, step.executed
+            )
+            .unwrap();
+            write_items(&mut json, &snapshot.main);
+            json.push_str(",\"alt\":");
+            write_items(&mut json, &snapshot.alt);
+            json.push_str(",\"cond\":[");
+            for (j, c) in snapshot.cond.iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                write!(json, "{c}").unwrap();
+            }
+            json.push_str("]}");
+        }
+        json.push_str("],\"error\":");
+        match &self.error {
+            Some(err) => write!(
+                json,
+                "{{\"kind\":\"{:?}\",\"opcode\":{},\"offset\":{}}}",
+                err.kind, err.opcode, err.offset
+            )
+            .unwrap(),
+            None => json.push_str("null"),
+        }
+        json.push('}');
+        json
+    }
+}
+
+fn write_items(json: &mut String, items: &[Vec<u8>]) {
+    json.push('[');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push('"');
+        for b in item {
+            write!(json, "{b:02x}").unwrap();
+        }
+        json.push('"');
+    }
+    json.push(']');
+}
@@ -15,64 +15,409 @@
 /// assert_eq!(stack.main.len(), 2);
 /// ```
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
-use alloc::string::ToString;
-use alloc::{format, string::String, vec, vec::Vec};
-use nom::IResult;
+mod asm;
+mod context;
+mod error;
+mod flags;
+mod instruction;
+mod interpreter;
+mod macros;
+mod nmacro;
+mod num;
+mod optimize;
+mod transaction;
+
+pub use asm::{from_asm, opcode_from_name, opcode_name, to_asm};
+pub use context::ExecutionContext;
+pub use error::{
+    AsmError, AsmErrorKind, EquivalenceError, MacroError, MacroErrorKind, MacroParseError,
+    MacroParseErrorKind, ScriptError, ScriptErrorKind,
+};
+pub use flags::{ELEMENT_OVERHEAD, ScriptFlags};
+pub use instruction::{Instruction, Instructions, PushKind, encode_push, parse_script};
+pub use interpreter::{Breakpoint, Halt, Interpreter, Snapshot, Step, Trace, TraceStep};
+pub use macros::{MacroArg, MacroDef, MacroElem, MacroRegistry, StackEffect, expand_macro};
+pub use nmacro::{parse_macros, std_macros};
+pub use num::ScriptNum;
+pub use optimize::{
+    OptLevel, RULES, Rule, check_equivalence, optimize, optimize_checked, optimize_with,
+};
+pub use transaction::{
+    OutPoint, SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_FORKID, SIGHASH_NONE, SIGHASH_SINGLE,
+    Transaction, TxInput, TxOutput,
+};
+
+use alloc::vec::Vec;
+use ripemd::Ripemd160;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 #[allow(unused_imports)]
 use sv::script::op_codes::{
-    OP_1, OP_16, OP_CAT, OP_CHECKMULTISIG, OP_CHECKSEQUENCEVERIFY, OP_CHECKSIG, OP_DROP, OP_DUP,
-    OP_EQUAL, OP_EQUALVERIFY, OP_FALSE, OP_HASH160, OP_PICK, OP_PUSHDATA1, OP_PUSHDATA2,
-    OP_PUSHDATA4, OP_ROLL, OP_SHA256, OP_SWAP,
+    OP_0NOTEQUAL, OP_1, OP_1ADD, OP_1NEGATE, OP_1SUB, OP_2DROP, OP_2DUP, OP_2OVER, OP_2ROT,
+    OP_2SWAP, OP_3DUP, OP_16, OP_ABS, OP_ADD, OP_AND, OP_BIN2NUM, OP_BOOLAND, OP_BOOLOR, OP_CAT,
+    OP_CHECKLOCKTIMEVERIFY, OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY, OP_CHECKSEQUENCEVERIFY,
+    OP_CHECKSIG, OP_CHECKSIGVERIFY, OP_CODESEPARATOR, OP_DEPTH, OP_DIV, OP_DROP, OP_DUP, OP_ELSE,
+    OP_ENDIF, OP_EQUAL, OP_EQUALVERIFY, OP_FALSE, OP_FROMALTSTACK, OP_GREATERTHAN,
+    OP_GREATERTHANOREQUAL, OP_HASH160, OP_HASH256, OP_IF, OP_IFDUP, OP_INVERT, OP_LESSTHAN,
+    OP_LESSTHANOREQUAL, OP_LSHIFT, OP_MAX, OP_MIN, OP_MOD, OP_MUL, OP_NEGATE, OP_NIP, OP_NOP,
+    OP_NOP1, OP_NOP4, OP_NOP10, OP_NOT, OP_NOTIF, OP_NUM2BIN, OP_NUMEQUAL, OP_NUMEQUALVERIFY,
+    OP_NUMNOTEQUAL, OP_OR, OP_OVER, OP_PICK, OP_PUSHDATA1, OP_PUSHDATA2, OP_PUSHDATA4, OP_RETURN,
+    OP_RIPEMD160, OP_ROLL, OP_ROT, OP_RSHIFT, OP_SHA1, OP_SHA256, OP_SIZE, OP_SPLIT, OP_SUB,
+    OP_SWAP, OP_TOALTSTACK, OP_TUCK, OP_VERIF, OP_VERIFY, OP_VERNOTIF, OP_WITHIN, OP_XOR,
 };
 
+/// Maximum byte length of a numeric operand before Genesis.
+pub const MAX_NUM_SIZE: usize = 4;
+
+/// Maximum byte length of a stack element before Genesis.
+pub const MAX_ELEMENT_SIZE: usize = 520;
+
+/// Decodes a script number (little-endian, sign-magnitude).
+///
+/// Operands longer than `max_len` bytes are rejected, as are non-minimal
+/// encodings (superfluous zero padding) when `require_minimal` is set.
+///
+/// ```
+/// use nprint_core::decode_num;
+///
+/// assert_eq!(decode_num(&[], 4, true), Ok(0));
+/// assert_eq!(decode_num(&[0x81], 4, true), Ok(-1));
+/// assert_eq!(decode_num(&[0xff, 0x00], 4, true), Ok(255));
+/// assert!(decode_num(&[0x01, 0x00], 4, true).is_err());
+/// ```
+pub fn decode_num(
+    bytes: &[u8],
+    max_len: usize,
+    require_minimal: bool,
+) -> Result<i64, ScriptErrorKind> {
+    ScriptNum::decode(bytes, max_len, require_minimal)?
+        .to_i64()
+        .ok_or(ScriptErrorKind::NumberOverflow)
+}
+
+/// Encodes a number in minimal script-number form (zero is the empty vector).
+///
+/// ```
+/// use nprint_core::encode_num;
+///
+/// assert_eq!(encode_num(0), Vec::<u8>::new());
+/// assert_eq!(encode_num(-1), vec![0x81]);
+/// assert_eq!(encode_num(128), vec![0x80, 0x00]);
+/// ```
+pub fn encode_num(n: i64) -> Vec<u8> {
+    ScriptNum::from(n).encode()
+}
+
+/// Strips superfluous zero padding from a script number, keeping its sign.
+///
+/// ```
+/// use nprint_core::minimally_encode;
+///
+/// assert_eq!(minimally_encode(vec![0x01, 0x00, 0x00, 0x80]), vec![0x81]);
+/// assert_eq!(minimally_encode(vec![0x80, 0x00]), vec![0x80, 0x00]);
+/// assert_eq!(minimally_encode(vec![0x00, 0x80]), Vec::<u8>::new());
+/// ```
+pub fn minimally_encode(mut data: Vec<u8>) -> Vec<u8> {
+    let Some(&last) = data.last() else {
+        return data;
+    };
+    if last & 0x7f != 0 {
+        return data;
+    }
+    // Drop trailing zero bytes, then re-attach the sign bit to the new last byte
+    match data[..data.len() - 1].iter().rposition(|b| *b != 0) {
+        Some(i) if data[i] & 0x80 != 0 => {
+            data.truncate(i + 2);
+            data[i + 1] = last;
+        }
+        Some(i) => {
+            data.truncate(i + 1);
+            data[i] |= last;
+        }
+        None => data.clear(),
+    }
+    data
+}
+
+/// Interprets a stack element as a boolean; zero and negative zero are false.
+pub fn decode_bool(bytes: &[u8]) -> bool {
+    match bytes.iter().position(|b| *b != 0) {
+        Some(i) => !(i == bytes.len() - 1 && bytes[i] == 0x80),
+        None => false,
+    }
+}
+
 /// Custom macro for BSV scripts as Vec<u8>.
-/// Supports u8 opcodes and i64 expressions (minimal push).
+///
+/// Comma-separated items, each one of:
+/// - an opcode name (`OP_ADD`, `OP_TRUE`, `OP_NOP2`, ...), emitted as its byte;
+/// - `hex!("...")` or a byte string `b"..."`, pushed as data with the
+///   smallest PUSHDATA encoding, or as OP_1 to OP_16 or OP_1NEGATE for the
+///   single bytes those opcodes push (see [`encode_push`]);
+/// - a script expression (`Vec<u8>`), such as a macro call like `xpick!(2)`
+///   or a nested [`loop_unroll!`], spliced in as it is;
+/// - any other integer expression, pushed minimally as a script number.
+///
+/// Unknown `OP_` identifiers and malformed hex fail to compile.
+///
+/// ```
+/// use nprint_core::bsv_script;
+///
+/// let n = 1000;
+/// let script = bsv_script! { OP_DUP, hex!("abcd"), b"hi", n, -1, OP_ADD };
+/// assert_eq!(script, [0x76, 2, 0xab, 0xcd, 2, b'h', b'i', 2, 0xe8, 0x03, 0x4f, 0x93]);
+/// ```
+///
+/// ```compile_fail
+/// let script = nprint_core::bsv_script! { OP_NOTANOPCODE };
+/// ```
+///
+/// ```compile_fail
+/// let script = nprint_core::bsv_script! { hex!("abc") };
+/// ```
 #[macro_export]
 macro_rules! bsv_script {
-    ($($token:tt),*) => {{
-        let mut script = Vec::new();
+    ($($item:expr),* $(,)?) => {{
+        let mut script: Vec<u8> = Vec::new();
+        $( $crate::__bsv_item!(script; $item); )*
+        script
+    }};
+}
+
+/// Appends one `bsv_script!` item, with opcode names and `hex!` resolved
+/// through [`__bsv`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bsv_item {
+    ($s:ident; $item:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__bsv::*;
+        $crate::ScriptLiteral::push_to($item, &mut $s);
+    }};
+}
+
+/// `hex!` inside `bsv_script!`: the decoded bytes, checked at compile time.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bsv_hex {
+    ($hex:literal) => {{
+        const DATA: [u8; $crate::__hex_len($hex)] = $crate::__hex($hex);
+        &DATA
+    }};
+}
+
+/// Names visible to `bsv_script!` items: every opcode name, including
+/// aliases, as an [`Opcode`](__bsv::Opcode), and `hex!`.
+#[doc(hidden)]
+pub mod __bsv {
+    pub use crate::__bsv_hex as hex;
+
+    /// Opcode emitted as its byte rather than pushed.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Opcode(pub u8);
+
+    impl crate::ScriptLiteral for Opcode {
+        fn push_to(self, script: &mut alloc::vec::Vec<u8>) {
+            script.push(self.0);
+        }
+    }
+
+    macro_rules! opcodes {
+        ($($name:ident = $op:literal,)*) => {
+            $(pub const $name: Opcode = Opcode($op);)*
+        };
+    }
+
+    opcodes! {
+        OP_0 = 0x00,
+        OP_PUSHDATA1 = 0x4c,
+        OP_PUSHDATA2 = 0x4d,
+        OP_PUSHDATA4 = 0x4e,
+        OP_1NEGATE = 0x4f,
+        OP_RESERVED = 0x50,
+        OP_1 = 0x51,
+        OP_2 = 0x52,
+        OP_3 = 0x53,
+        OP_4 = 0x54,
+        OP_5 = 0x55,
+        OP_6 = 0x56,
+        OP_7 = 0x57,
+        OP_8 = 0x58,
+        OP_9 = 0x59,
+        OP_10 = 0x5a,
+        OP_11 = 0x5b,
+        OP_12 = 0x5c,
+        OP_13 = 0x5d,
+        OP_14 = 0x5e,
+        OP_15 = 0x5f,
+        OP_16 = 0x60,
+        OP_NOP = 0x61,
+        OP_VER = 0x62,
+        OP_IF = 0x63,
+        OP_NOTIF = 0x64,
+        OP_VERIF = 0x65,
+        OP_VERNOTIF = 0x66,
+        OP_ELSE = 0x67,
+        OP_ENDIF = 0x68,
+        OP_VERIFY = 0x69,
+        OP_RETURN = 0x6a,
+        OP_TOALTSTACK = 0x6b,
+        OP_FROMALTSTACK = 0x6c,
+        OP_2DROP = 0x6d,
+        OP_2DUP = 0x6e,
+        OP_3DUP = 0x6f,
+        OP_2OVER = 0x70,
+        OP_2ROT = 0x71,
+        OP_2SWAP = 0x72,
+        OP_IFDUP = 0x73,
+        OP_DEPTH = 0x74,
+        OP_DROP = 0x75,
+        OP_DUP = 0x76,
+        OP_NIP = 0x77,
+        OP_OVER = 0x78,
+        OP_PICK = 0x79,
+        OP_ROLL = 0x7a,
+        OP_ROT = 0x7b,
+        OP_SWAP = 0x7c,
+        OP_TUCK = 0x7d,
+        OP_CAT = 0x7e,
+        OP_SPLIT = 0x7f,
+        OP_NUM2BIN = 0x80,
+        OP_BIN2NUM = 0x81,
+        OP_SIZE = 0x82,
+        OP_INVERT = 0x83,
+        OP_AND = 0x84,
+        OP_OR = 0x85,
+        OP_XOR = 0x86,
+        OP_EQUAL = 0x87,
+        OP_EQUALVERIFY = 0x88,
+        OP_RESERVED1 = 0x89,
+        OP_RESERVED2 = 0x8a,
+        OP_1ADD = 0x8b,
+        OP_1SUB = 0x8c,
+        OP_2MUL = 0x8d,
+        OP_2DIV = 0x8e,
+        OP_NEGATE = 0x8f,
+        OP_ABS = 0x90,
+        OP_NOT = 0x91,
+        OP_0NOTEQUAL = 0x92,
+        OP_ADD = 0x93,
+        OP_SUB = 0x94,
+        OP_MUL = 0x95,
+        OP_DIV = 0x96,
+        OP_MOD = 0x97,
+        OP_LSHIFT = 0x98,
+        OP_RSHIFT = 0x99,
+        OP_BOOLAND = 0x9a,
+        OP_BOOLOR = 0x9b,
+        OP_NUMEQUAL = 0x9c,
+        OP_NUMEQUALVERIFY = 0x9d,
+        OP_NUMNOTEQUAL = 0x9e,
+        OP_LESSTHAN = 0x9f,
+        OP_GREATERTHAN = 0xa0,
+        OP_LESSTHANOREQUAL = 0xa1,
+        OP_GREATERTHANOREQUAL = 0xa2,
+        OP_MIN = 0xa3,
+        OP_MAX = 0xa4,
+        OP_WITHIN = 0xa5,
+        OP_RIPEMD160 = 0xa6,
+        OP_SHA1 = 0xa7,
+        OP_SHA256 = 0xa8,
+        OP_HASH160 = 0xa9,
+        OP_HASH256 = 0xaa,
+        OP_CODESEPARATOR = 0xab,
+        OP_CHECKSIG = 0xac,
+        OP_CHECKSIGVERIFY = 0xad,
+        OP_CHECKMULTISIG = 0xae,
+        OP_CHECKMULTISIGVERIFY = 0xaf,
+        OP_NOP1 = 0xb0,
+        OP_CHECKLOCKTIMEVERIFY = 0xb1,
+        OP_CHECKSEQUENCEVERIFY = 0xb2,
+        OP_NOP4 = 0xb3,
+        OP_NOP5 = 0xb4,
+        OP_NOP6 = 0xb5,
+        OP_NOP7 = 0xb6,
+        OP_NOP8 = 0xb7,
+        OP_NOP9 = 0xb8,
+        OP_NOP10 = 0xb9,
+        OP_FALSE = 0x00,
+        OP_TRUE = 0x51,
+        OP_NOP2 = 0xb1,
+        OP_NOP3 = 0xb2,
+    }
+}
+
+/// Value `bsv_script!` can push: integers as minimal script numbers, byte
+/// strings as minimal data pushes, scripts spliced in as they are.
+#[doc(hidden)]
+pub trait ScriptLiteral {
+    fn push_to(self, script: &mut Vec<u8>);
+}
+
+macro_rules! impl_script_literal {
+    ($($t:ty),*) => {
         $(
-            match stringify!($token) {
-                // Handle known opcodes explicitly
-                "OP_DUP" => script.push(sv::script::op_codes::OP_DUP),
-                "OP_SWAP" => script.push(sv::script::op_codes::OP_SWAP),
-                "OP_PICK" => script.push(sv::script::op_codes::OP_PICK),
-                "OP_ROLL" => script.push(sv::script::op_codes::OP_ROLL),
-                "OP_DROP" => script.push(sv::script::op_codes::OP_DROP),
-                "OP_HASH160" => script.push(sv::script::op_codes::OP_HASH160),
-                "OP_CAT" => script.push(sv::script::op_codes::OP_CAT),
-                "OP_1" => script.push(sv::script::op_codes::OP_1),
-                "OP_FALSE" => script.push(sv::script::op_codes::OP_FALSE),
-                "OP_PUSHDATA1" => script.push(sv::script::op_codes::OP_PUSHDATA1),
-                "OP_PUSHDATA2" => script.push(sv::script::op_codes::OP_PUSHDATA2),
-                "OP_PUSHDATA4" => script.push(sv::script::op_codes::OP_PUSHDATA4),
-                "OP_16" => script.push(sv::script::op_codes::OP_16),
-                "OP_EQUALVERIFY" => script.push(sv::script::op_codes::OP_EQUALVERIFY),
-                "OP_CHECKSIG" => script.push(sv::script::op_codes::OP_CHECKSIG),
-                "OP_CHECKMULTISIG" => script.push(sv::script::op_codes::OP_CHECKMULTISIG),
-                "OP_CHECKSEQUENCEVERIFY" => script.push(sv::script::op_codes::OP_CHECKSEQUENCEVERIFY),
-                "OP_SHA256" => script.push(sv::script::op_codes::OP_SHA256),
-                "OP_EQUAL" => script.push(sv::script::op_codes::OP_EQUAL),
-                _ => {
-                    // Handle any expression that evaluates to i64
-                    let n: i64 = $token as i64;
-                    if (0..=16).contains(&n) {
-                        script.push(sv::script::op_codes::OP_1 + (n as u8 - 1));
-                    } else {
-                        match sv::script::stack::encode_num(n) {
-                            Ok(bytes) => {
-                                script.push(bytes.len() as u8);
-                                script.extend_from_slice(&bytes);
-                            }
-                            Err(e) => panic!("Failed to encode number: {e}"),
-                        }
-                    }
+            impl ScriptLiteral for $t {
+                fn push_to(self, script: &mut Vec<u8>) {
+                    script.extend(ScriptNum::from(self).to_push());
                 }
             }
         )*
-        script
-    }};
+    };
+}
+
+impl_script_literal!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+impl<const N: usize> ScriptLiteral for &[u8; N] {
+    fn push_to(self, script: &mut Vec<u8>) {
+        script.extend(encode_push(self, true));
+    }
+}
+
+impl ScriptLiteral for &[u8] {
+    fn push_to(self, script: &mut Vec<u8>) {
+        script.extend(encode_push(self, true));
+    }
+}
+
+/// A script, such as a macro's expansion, is spliced in rather than pushed.
+impl ScriptLiteral for Vec<u8> {
+    fn push_to(self, script: &mut Vec<u8>) {
+        script.extend(self);
+    }
+}
+
+#[doc(hidden)]
+pub const fn __hex_len(hex: &str) -> usize {
+    if !hex.len().is_multiple_of(2) {
+        panic!("hex!: odd number of digits");
+    }
+    hex.len() / 2
+}
+
+#[doc(hidden)]
+pub const fn __hex<const N: usize>(hex: &str) -> [u8; N] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            b'A'..=b'F' => c - b'A' + 10,
+            _ => panic!("hex!: invalid digit"),
+        }
+    }
+    let hex = hex.as_bytes();
+    let mut out = [0; N];
+    let mut i = 0;
+    while i < N {
+        out[i] = (nibble(hex[2 * i]) << 4) | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    out
 }
 
 /// Stack model: Simulates main and alt stacks as 2PDA.
@@ -91,160 +436,513 @@ macro_rules! bsv_script {
 pub struct Stack {
     pub main: Vec<Vec<u8>>,
     pub alt: Vec<Vec<u8>>,
+    /// Condition stack: one entry per open OP_IF/OP_NOTIF, true if its branch executes.
+    pub cond: Vec<bool>,
+    /// Rule set and limits applied by `execute`.
+    pub flags: ScriptFlags,
+    /// Combined main and alt stack memory charged against
+    /// `flags.max_stack_memory`. Kept current by execution and recounted
+    /// whenever a script starts, so the stacks may be edited directly between
+    /// scripts.
+    pub memory: usize,
 }
 
 impl Stack {
     pub fn push(&mut self, value: Vec<u8>) {
+        self.memory += value.len() + ELEMENT_OVERHEAD;
         self.main.push(value);
     }
-    pub fn pop(&mut self) -> Vec<u8> {
-        self.main.pop().expect("Stack underflow")
+
+    /// Pops the top of the main stack.
+    pub fn pop(&mut self) -> Result<Vec<u8>, ScriptErrorKind> {
+        let top = self.main.pop().ok_or(ScriptErrorKind::StackUnderflow)?;
+        self.release(&top);
+        Ok(top)
+    }
+
+    /// Stops charging for `item`, which has left the stacks.
+    fn release(&mut self, item: &[u8]) {
+        self.memory = self.memory.saturating_sub(item.len() + ELEMENT_OVERHEAD);
+    }
+
+    /// Recounts `memory` from the stacks.
+    pub(crate) fn recount_memory(&mut self) {
+        self.memory = self
+            .main
+            .iter()
+            .chain(&self.alt)
+            .map(|item| item.len() + ELEMENT_OVERHEAD)
+            .sum();
+    }
+
+    /// Returns the main stack depth, failing if it holds fewer than `n` items.
+    fn require(&self, n: usize) -> Result<usize, ScriptErrorKind> {
+        let len = self.main.len();
+        if len < n {
+            return Err(ScriptErrorKind::StackUnderflow);
+        }
+        Ok(len)
+    }
+
+    /// Pops a numeric operand, minimally encoded if the flags require it.
+    fn pop_script_num(&mut self) -> Result<ScriptNum, ScriptErrorKind> {
+        let top = self.pop()?;
+        ScriptNum::decode(&top, self.flags.max_num_size, self.flags.require_minimal)
+    }
+
+    /// Pops a number used as a count, index or size.
+    fn pop_num(&mut self) -> Result<i64, ScriptErrorKind> {
+        self.pop_script_num()?
+            .to_i64()
+            .ok_or(ScriptErrorKind::InvalidNumberRange)
+    }
+
+    /// Checks the stack against the item and memory limits.
+    fn check_stack_size(&self) -> Result<(), ScriptErrorKind> {
+        let flags = &self.flags;
+        if self.main.len() + self.alt.len() > flags.max_stack_items {
+            return Err(ScriptErrorKind::StackSizeExceeded);
+        }
+        if self.memory > flags.max_stack_memory {
+            return Err(ScriptErrorKind::StackSizeExceeded);
+        }
+        Ok(())
     }
 
     /// Symbolic execution for verification.
-    pub fn execute(&mut self, script: &[u8]) -> Result<(), String> {
-        let mut i = 0;
-        while i < script.len() {
-            let op = script[i];
-            i += 1;
-            match op {
-                op if (OP_1..=OP_16).contains(&op) => {
-                    // Push small integers (1 to 16)
-                    let value = (op - (OP_1 - 1)) as i64;
-                    let bytes = sv::script::stack::encode_num(value)
-                        .map_err(|e| format!("Failed to encode number: {e}"))?;
-                    self.push(bytes);
+    ///
+    /// Branches not taken by OP_IF/OP_NOTIF/OP_ELSE are skipped, including
+    /// their push data; the script fails if its conditionals are unbalanced.
+    /// Limits, disabled opcodes and OP_RETURN semantics follow `self.flags`.
+    /// Signature and lock time opcodes fail with
+    /// [`ScriptErrorKind::MissingContext`]; use [`Stack::execute_with`] to run them.
+    pub fn execute(&mut self, script: &[u8]) -> Result<(), ScriptError> {
+        Interpreter::new(self, script).run()
+    }
+
+    /// Executes `script` as part of the spend described by `ctx`, enabling
+    /// OP_CHECKSIG, OP_CHECKMULTISIG and their VERIFY forms, and
+    /// OP_CHECKLOCKTIMEVERIFY/OP_CHECKSEQUENCEVERIFY.
+    pub fn execute_with(
+        &mut self,
+        script: &[u8],
+        ctx: &ExecutionContext,
+    ) -> Result<(), ScriptError> {
+        Interpreter::new(self, script).with_context(ctx).run()
+    }
+
+    /// Executes OP_CHECKSIG, OP_CHECKMULTISIG or their VERIFY forms.
+    fn execute_checksig(
+        &mut self,
+        op: u8,
+        ctx: &ExecutionContext,
+        script_code: &[u8],
+    ) -> Result<(), ScriptErrorKind> {
+        let success = if op == OP_CHECKSIG || op == OP_CHECKSIGVERIFY {
+            self.require(2)?;
+            let pubkey = self.pop()?;
+            let sig = self.pop()?;
+            ctx.check_sig(&sig, &pubkey, script_code)?
+        } else {
+            let keys = self.pop_count()?;
+            let sigs = self.pop_count()?;
+            if sigs.len() > keys.len() {
+                return Err(ScriptErrorKind::InvalidNumberRange);
+            }
+            self.pop()?; // Extra element consumed by the original implementation
+            // Signatures must appear in the same order as their keys
+            let mut keys = keys.iter();
+            let mut success = true;
+            for (i, sig) in sigs.iter().enumerate() {
+                let remaining = sigs.len() - i;
+                let matched = loop {
+                    if keys.len() < remaining {
+                        break false;
+                    }
+                    let key = keys.next().unwrap();
+                    if ctx.check_sig(sig, key, script_code)? {
+                        break true;
+                    }
+                };
+                if !matched {
+                    success = false;
+                    break;
+                }
+            }
+            success
+        };
+        if op == OP_CHECKSIGVERIFY || op == OP_CHECKMULTISIGVERIFY {
+            if !success {
+                return Err(ScriptErrorKind::VerifyFailed);
+            }
+        } else {
+            self.push(encode_num(success as i64));
+        }
+        Ok(())
+    }
+
+    /// Executes OP_CHECKLOCKTIMEVERIFY or OP_CHECKSEQUENCEVERIFY, leaving
+    /// the lock time on the stack.
+    fn execute_locktime(&mut self, op: u8, ctx: &ExecutionContext) -> Result<(), ScriptErrorKind> {
+        let top = self.main.last().ok_or(ScriptErrorKind::StackUnderflow)?;
+        // Lock times are five-byte numbers, so they can reach 2^32 - 1
+        let lock_time = decode_num(top, 5, self.flags.require_minimal)?;
+        if lock_time < 0 {
+            return Err(ScriptErrorKind::NegativeLocktime);
+        }
+        let satisfied = if op == OP_CHECKLOCKTIMEVERIFY {
+            ctx.check_lock_time(lock_time)?
+        } else {
+            // The disable flag makes the lock a NOP, leaving it for future soft forks
+            lock_time & (1 << 31) != 0 || ctx.check_sequence(lock_time)?
+        };
+        if !satisfied {
+            return Err(ScriptErrorKind::UnsatisfiedLocktime);
+        }
+        Ok(())
+    }
+
+    /// Pops a count `n` followed by `n` items, returned top first.
+    fn pop_count(&mut self) -> Result<Vec<Vec<u8>>, ScriptErrorKind> {
+        let n = self.pop_num()?;
+        if n < 0 {
+            return Err(ScriptErrorKind::InvalidNumberRange);
+        }
+        let len = self.require(n as usize)?;
+        let mut items = self.main.split_off(len - n as usize);
+        items.iter().for_each(|item| self.release(item));
+        items.reverse();
+        Ok(items)
+    }
+
+    /// Executes a single non-push opcode other than OP_RETURN.
+    fn execute_op(&mut self, op: u8, executing: bool) -> Result<(), ScriptErrorKind> {
+        match op {
+            OP_IF | OP_NOTIF => {
+                let mut value = false;
+                if executing {
+                    value = decode_bool(&self.pop()?) == (op == OP_IF);
                 }
-                OP_DUP => {
-                    let top = self.main.last().cloned().ok_or("Dup underflow")?;
-                    self.push(top);
+                self.cond.push(value);
+            }
+            OP_ELSE => {
+                let top = self
+                    .cond
+                    .last_mut()
+                    .ok_or(ScriptErrorKind::UnbalancedConditional)?;
+                *top = !*top;
+            }
+            OP_ENDIF => {
+                self.cond
+                    .pop()
+                    .ok_or(ScriptErrorKind::UnbalancedConditional)?;
+            }
+            // Rejected even in unexecuted branches, unlike other invalid opcodes
+            OP_VERIF | OP_VERNOTIF => return Err(ScriptErrorKind::InvalidOpcode),
+            _ if !executing => {}
+            // Includes OP_CHECKLOCKTIMEVERIFY and OP_CHECKSEQUENCEVERIFY after Genesis
+            OP_NOP | OP_NOP1..=OP_NOP10 => {}
+            OP_VERIFY => {
+                let top = self.pop()?;
+                if !decode_bool(&top) {
+                    return Err(ScriptErrorKind::VerifyFailed);
                 }
-                OP_SWAP => {
-                    let a = self.pop();
-                    let b = self.pop();
-                    self.push(a);
-                    self.push(b);
+            }
+            OP_1NEGATE => self.push(encode_num(-1)),
+            op if (OP_1..=OP_16).contains(&op) => {
+                // Push small integers (1 to 16)
+                let value = (op - (OP_1 - 1)) as i64;
+                self.push(encode_num(value));
+            }
+            OP_DUP => {
+                let top = self
+                    .main
+                    .last()
+                    .cloned()
+                    .ok_or(ScriptErrorKind::StackUnderflow)?;
+                self.push(top);
+            }
+            OP_SWAP => {
+                let len = self.require(2)?;
+                self.main.swap(len - 1, len - 2);
+            }
+            OP_PICK => {
+                let n = self.pop_num()?;
+                let len = self.main.len();
+                // Consensus reports an index outside the stack as an invalid stack operation
+                if n < 0 || n as usize >= len {
+                    return Err(ScriptErrorKind::StackUnderflow);
                 }
-                OP_PICK => {
-                    let n = self.pop()[0] as usize;
-                    if n > self.main.len() {
-                        return Err("Pick underflow".to_string());
-                    }
-                    let item = self
-                        .main
-                        .get(self.main.len() - n)
-                        .cloned()
-                        .ok_or("Pick underflow")?;
-                    self.push(item);
+                self.push(self.main[len - 1 - n as usize].clone()); // Copy nth item from top to top
+            }
+            OP_ROLL => {
+                let n = self.pop_num()?;
+                let len = self.main.len();
+                if n < 0 || n as usize >= len {
+                    return Err(ScriptErrorKind::StackUnderflow);
                 }
-                OP_ROLL => {
-                    let n = self.pop()[0] as usize;
-                    if n >= self.main.len() {
-                        return Err("Roll underflow".to_string());
-                    }
-                    let item = self.main.remove(self.main.len() - 1 - n); // Move nth item from top to top
-                    self.push(item);
+                let item = self.main.remove(len - 1 - n as usize); // Move nth item from top to top
+                self.main.push(item);
+            }
+            OP_DROP => {
+                self.pop()?;
+            }
+            // Moving between the stacks leaves the memory used unchanged
+            OP_TOALTSTACK => {
+                let top = self.main.pop().ok_or(ScriptErrorKind::StackUnderflow)?;
+                self.alt.push(top);
+            }
+            OP_FROMALTSTACK => {
+                let top = self.alt.pop().ok_or(ScriptErrorKind::AltStackUnderflow)?;
+                self.main.push(top);
+            }
+            OP_2DROP => {
+                self.require(2)?;
+                self.pop()?;
+                self.pop()?;
+            }
+            OP_2DUP | OP_3DUP | OP_2OVER => {
+                // (items copied, depth of the first copied item)
+                let (count, depth) = match op {
+                    OP_2DUP => (2, 2),
+                    OP_3DUP => (3, 3),
+                    _ => (2, 4),
+                };
+                let len = self.require(depth)?;
+                for i in len - depth..len - depth + count {
+                    self.push(self.main[i].clone());
                 }
-                OP_DROP => {
-                    let _ = self.pop();
+            }
+            OP_2ROT => {
+                let len = self.require(6)?;
+                let items: Vec<_> = self.main.drain(len - 6..len - 4).collect();
+                self.main.extend(items);
+            }
+            OP_2SWAP => {
+                let len = self.require(4)?;
+                self.main[len - 4..].rotate_left(2);
+            }
+            OP_IFDUP => {
+                let top = self.main.last().ok_or(ScriptErrorKind::StackUnderflow)?;
+                if decode_bool(top) {
+                    self.push(top.clone());
                 }
-                OP_PUSHDATA1 => {
-                    if i + 1 > script.len() {
-                        return Err("PUSHDATA1 length byte missing".to_string());
-                    }
-                    let len = self.pop()[0] as usize;
-                    i += 1;
-                    if i + len > script.len() {
-                        return Err("PUSHDATA1 data exceeds script length".to_string());
-                    }
-                    let data = script[i..i + len].to_vec();
-                    self.push(data);
-                    i += len;
+            }
+            OP_DEPTH => self.push(encode_num(self.main.len() as i64)),
+            OP_NIP => {
+                let len = self.require(2)?;
+                let item = self.main.remove(len - 2);
+                self.release(&item);
+            }
+            OP_OVER => {
+                let len = self.require(2)?;
+                self.push(self.main[len - 2].clone());
+            }
+            OP_ROT => {
+                let len = self.require(3)?;
+                self.main[len - 3..].rotate_left(1);
+            }
+            OP_TUCK => {
+                let len = self.require(2)?;
+                let top = self.main[len - 1].clone();
+                self.memory += top.len() + ELEMENT_OVERHEAD;
+                self.main.insert(len - 2, top);
+            }
+            OP_EQUAL | OP_EQUALVERIFY => {
+                let a = self.pop()?;
+                let b = self.pop()?;
+                if op == OP_EQUAL {
+                    self.push(encode_num((a == b) as i64));
+                } else if a != b {
+                    return Err(ScriptErrorKind::VerifyFailed);
                 }
-                OP_PUSHDATA2 => {
-                    if i + 2 > script.len() {
-                        return Err("PUSHDATA2 length bytes missing".to_string());
-                    }
-                    let len = u16::from_le_bytes([script[i], script[i + 1]]) as usize;
-                    i += 2;
-                    if i + len > script.len() {
-                        return Err("PUSHDATA2 data exceeds script length".to_string());
-                    }
-                    let data = script[i..i + len].to_vec();
-                    self.push(data);
-                    i += len;
+            }
+            OP_CAT => {
+                let b = self.pop()?;
+                let mut a = self.pop()?;
+                if a.len() + b.len() > self.flags.max_element_size {
+                    return Err(ScriptErrorKind::PushSizeExceeded);
                 }
-                OP_PUSHDATA4 => {
-                    if i + 4 > script.len() {
-                        return Err("PUSHDATA4 length bytes missing".to_string());
-                    }
-                    let len = u32::from_le_bytes([
-                        script[i],
-                        script[i + 1],
-                        script[i + 2],
-                        script[i + 3],
-                    ]) as usize;
-                    i += 4;
-                    if i + len > script.len() {
-                        return Err("PUSHDATA4 data exceeds script length".to_string());
-                    }
-                    let data = script[i..i + len].to_vec();
-                    self.push(data);
-                    i += len;
+                a.extend(b);
+                self.push(a);
+            }
+            OP_SPLIT => {
+                let n = self.pop_num()?;
+                let mut data = self.pop()?;
+                if n < 0 || n as usize > data.len() {
+                    return Err(ScriptErrorKind::InvalidNumberRange);
+                }
+                let tail = data.split_off(n as usize);
+                self.push(data);
+                self.push(tail);
+            }
+            OP_NUM2BIN => {
+                let size = self.pop_num()?;
+                if size < 0 || size as usize > self.flags.max_element_size {
+                    return Err(ScriptErrorKind::PushSizeExceeded);
+                }
+                let size = size as usize;
+                let mut num = minimally_encode(self.pop()?);
+                if num.len() > size {
+                    return Err(ScriptErrorKind::ImpossibleEncoding);
+                }
+                if num.len() < size {
+                    // Move the sign bit from the current last byte to the new one
+                    let sign = num.last_mut().map_or(0, |last| {
+                        let sign = *last & 0x80;
+                        *last &= 0x7f;
+                        sign
+                    });
+                    num.resize(size - 1, 0);
+                    num.push(sign);
+                }
+                self.push(num);
+            }
+            OP_BIN2NUM => {
+                let num = minimally_encode(self.pop()?);
+                if num.len() > self.flags.max_num_size {
+                    return Err(ScriptErrorKind::NumberOverflow);
+                }
+                self.push(num);
+            }
+            OP_SIZE => {
+                let len = self
+                    .main
+                    .last()
+                    .ok_or(ScriptErrorKind::StackUnderflow)?
+                    .len();
+                self.push(encode_num(len as i64));
+            }
+            OP_INVERT => {
+                let top = self
+                    .main
+                    .last_mut()
+                    .ok_or(ScriptErrorKind::StackUnderflow)?;
+                top.iter_mut().for_each(|b| *b = !*b);
+            }
+            OP_AND | OP_OR | OP_XOR => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                if a.len() != b.len() {
+                    return Err(ScriptErrorKind::OperandSizeMismatch);
+                }
+                let result = a.iter().zip(&b).map(|(x, y)| match op {
+                    OP_AND => x & y,
+                    OP_OR => x | y,
+                    _ => x ^ y,
+                });
+                self.push(result.collect());
+            }
+            OP_LSHIFT | OP_RSHIFT => {
+                let n = self.pop_num()?;
+                let data = self.pop()?;
+                if n < 0 {
+                    return Err(ScriptErrorKind::InvalidNumberRange);
                 }
-                op if op > 0 && op <= 75 => {
-                    // Direct push of data (length <= 75, excluding OP_FALSE)
-                    if i + op as usize > script.len() {
-                        return Err("Push data exceeds script length".to_string());
+                self.push(shift_bits(&data, n as usize, op == OP_LSHIFT));
+            }
+            OP_RIPEMD160 | OP_SHA1 | OP_SHA256 | OP_HASH160 | OP_HASH256 => {
+                let top = self.pop()?;
+                let digest = match op {
+                    OP_RIPEMD160 => Ripemd160::digest(&top).to_vec(),
+                    OP_SHA1 => Sha1::digest(&top).to_vec(),
+                    OP_SHA256 => Sha256::digest(&top).to_vec(),
+                    OP_HASH160 => Ripemd160::digest(Sha256::digest(&top)).to_vec(),
+                    _ => Sha256::digest(Sha256::digest(&top)).to_vec(),
+                };
+                self.push(digest);
+            }
+            OP_1ADD | OP_1SUB | OP_NEGATE | OP_ABS | OP_NOT | OP_0NOTEQUAL => {
+                let a = self.pop_script_num()?;
+                let result = match op {
+                    OP_1ADD => a + ScriptNum::from(1),
+                    OP_1SUB => a - ScriptNum::from(1),
+                    OP_NEGATE => -a,
+                    OP_ABS => a.abs(),
+                    OP_NOT => ScriptNum::from(a.is_zero()),
+                    _ => ScriptNum::from(!a.is_zero()),
+                };
+                self.push(result.encode());
+            }
+            OP_ADD
+            | OP_SUB
+            | OP_MUL
+            | OP_DIV
+            | OP_MOD
+            | OP_BOOLAND
+            | OP_BOOLOR
+            | OP_NUMEQUAL
+            | OP_NUMEQUALVERIFY
+            | OP_NUMNOTEQUAL
+            | OP_LESSTHAN
+            | OP_GREATERTHAN
+            | OP_LESSTHANOREQUAL
+            | OP_GREATERTHANOREQUAL
+            | OP_MIN
+            | OP_MAX => {
+                let b = self.pop_script_num()?;
+                let a = self.pop_script_num()?;
+                let result = match op {
+                    OP_ADD => a + b,
+                    OP_SUB => a - b,
+                    OP_MUL => a * b,
+                    // Truncating division; remainder takes the sign of the dividend
+                    OP_DIV => a.checked_div(&b).ok_or(ScriptErrorKind::DivisionByZero)?,
+                    OP_MOD => a.checked_rem(&b).ok_or(ScriptErrorKind::DivisionByZero)?,
+                    OP_BOOLAND => ScriptNum::from(!a.is_zero() && !b.is_zero()),
+                    OP_BOOLOR => ScriptNum::from(!a.is_zero() || !b.is_zero()),
+                    OP_NUMEQUAL | OP_NUMEQUALVERIFY => ScriptNum::from(a == b),
+                    OP_NUMNOTEQUAL => ScriptNum::from(a != b),
+                    OP_LESSTHAN => ScriptNum::from(a < b),
+                    OP_GREATERTHAN => ScriptNum::from(a > b),
+                    OP_LESSTHANOREQUAL => ScriptNum::from(a <= b),
+                    OP_GREATERTHANOREQUAL => ScriptNum::from(a >= b),
+                    OP_MIN => a.min(b),
+                    _ => a.max(b),
+                };
+                if op == OP_NUMEQUALVERIFY {
+                    if result.is_zero() {
+                        return Err(ScriptErrorKind::VerifyFailed);
                     }
-                    let data = script[i..i + op as usize].to_vec();
-                    self.push(data);
-                    i += op as usize;
+                } else {
+                    self.push(result.encode());
                 }
-                op => return Err(format!("Unsupported op: {op}")),
             }
+            OP_WITHIN => {
+                let max = self.pop_script_num()?;
+                let min = self.pop_script_num()?;
+                let x = self.pop_script_num()?;
+                self.push(encode_num((min <= x && x < max) as i64));
+            }
+            _ => return Err(ScriptErrorKind::InvalidOpcode),
         }
         Ok(())
     }
 }
 
-/// Macro element: Opcode or parameter.
-#[derive(Clone, Debug)]
-pub enum MacroElem {
-    Op(u8),
-    Param(usize),
-}
-
-/// Macro definition: (name, params, template) per article.
-#[derive(Clone, Debug)]
-pub struct MacroDef {
-    pub name: String,
-    pub param_count: usize,
-    pub template: Vec<MacroElem>,
-}
-
-/// Expand macro hygienically.
-pub fn expand_macro(def: &MacroDef, args: &[i32]) -> Vec<u8> {
-    if args.len() != def.param_count {
-        panic!("Arg mismatch");
-    }
-    let mut expanded = Vec::new();
-    for elem in &def.template {
-        match elem {
-            MacroElem::Op(op) => expanded.push(*op),
-            MacroElem::Param(idx) => {
-                let n = args[*idx];
-                if (0..=16).contains(&n) {
-                    expanded.push(OP_1 - 1 + n as u8);
-                } else {
-                    let bytes = sv::script::stack::encode_num(n as i64).unwrap();
-                    expanded.push(bytes.len() as u8);
-                    expanded.extend(bytes);
-                }
+/// Shifts `data`, read as a big-endian bit string, by `n` bits.
+///
+/// The shift is logical: the element keeps its length, vacated bits are
+/// zero and the sign bit receives no special treatment.
+fn shift_bits(data: &[u8], n: usize, left: bool) -> Vec<u8> {
+    let (bytes, bits) = (n / 8, (n % 8) as u32);
+    let at = |i: Option<usize>| i.and_then(|i| data.get(i)).copied().unwrap_or(0);
+    (0..data.len())
+        .map(|i| {
+            // `near` supplies the high bits of output byte i, `far` the carried bits
+            let (near, far) = if left {
+                let src = i.checked_add(bytes);
+                (at(src), at(src.and_then(|s| s.checked_add(1))))
+            } else {
+                (at(i.checked_sub(bytes)), at(i.checked_sub(bytes + 1)))
+            };
+            match (bits, left) {
+                (0, _) => near,
+                (_, true) => (near << bits) | (far >> (8 - bits)),
+                (_, false) => (near >> bits) | (far << (8 - bits)),
             }
-        }
-    }
-    expanded
+        })
+        .collect()
 }
 
 /// OP_XSWAP_n: Swaps top with nth item.
@@ -252,7 +950,7 @@ pub fn expand_macro(def: &MacroDef, args: &[i32]) -> Vec<u8> {
 #[macro_export]
 macro_rules! xswap {
     ($n:expr) => {
-        bsv_script! { ($n - 1), OP_ROLL }
+        $crate::bsv_script! { ($n - 1), OP_ROLL }
     };
 }
 
@@ -261,7 +959,7 @@ macro_rules! xswap {
 #[macro_export]
 macro_rules! xdrop {
     ($n:expr) => {
-        bsv_script! { $n - 1, OP_ROLL, OP_DROP }
+        $crate::bsv_script! { $n - 1, OP_ROLL, OP_DROP }
     };
 }
 
@@ -270,7 +968,7 @@ macro_rules! xdrop {
 #[macro_export]
 macro_rules! xrot {
     ($n:expr) => {
-        bsv_script! { $n, OP_ROLL }
+        $crate::bsv_script! { $n, OP_ROLL }
     };
 }
 
@@ -279,26 +977,173 @@ macro_rules! xrot {
 #[macro_export]
 macro_rules! hashcat {
     () => {
-        bsv_script! { OP_DUP, OP_HASH160, OP_CAT }
+        $crate::bsv_script! { OP_DUP, OP_HASH160, OP_CAT }
     };
 }
 
+/// Upper bound on the iterations of a single [`loop_unroll!`] with an index
+/// binding, checked at compile time, and of a single [`MacroElem::Repeat`],
+/// checked at expansion.
+pub const MAX_UNROLL: usize = 1000;
+
 /// LOOP[n]{body}: Unrolls body n times statically.
 /// Example: loop_unroll!(3, { OP_DUP }) -> [OP_DUP, OP_DUP, OP_DUP].
+///
+/// With `i in start..end` (or `start..=end`) the body sees the iteration
+/// number as `i`, pushed minimally wherever it appears as an item. The bounds
+/// are single tokens (parenthesize anything longer) and must be constants:
+/// loops of more than [`MAX_UNROLL`] iterations fail to compile. Loops nest
+/// with distinct index names.
+///
+/// ```
+/// use nprint_core::loop_unroll;
+///
+/// const N: u8 = 3;
+/// let script = loop_unroll!(i in 0..N, { i, OP_PICK, OP_SHA256 });
+/// assert_eq!(script, [0x00, 0x79, 0xa8, 0x51, 0x79, 0xa8, 0x52, 0x79, 0xa8]);
+///
+/// let script = loop_unroll!(i in 1..=2, { loop_unroll!(j in 0..2, { i, j, OP_ADD }) });
+/// assert_eq!(
+///     script,
+///     [0x51, 0x00, 0x93, 0x51, 0x51, 0x93, 0x52, 0x00, 0x93, 0x52, 0x51, 0x93]
+/// );
+/// ```
+///
+/// ```compile_fail
+/// let script = nprint_core::loop_unroll!(i in 0..100_000, { i, OP_DROP });
+/// ```
 #[macro_export]
 macro_rules! loop_unroll {
+    ($i:ident in $start:tt ..= $end:tt, { $($body:tt)* }) => {{
+        const _: () = $crate::__check_unroll($start as i128, $end as i128 + 1);
+        let mut script = Vec::new();
+        for $i in $start..=$end {
+            script.extend($crate::bsv_script! { $($body)* });
+        }
+        script
+    }};
+    ($i:ident in $start:tt .. $end:tt, { $($body:tt)* }) => {{
+        const _: () = $crate::__check_unroll($start as i128, $end as i128);
+        let mut script = Vec::new();
+        for $i in $start..$end {
+            script.extend($crate::bsv_script! { $($body)* });
+        }
+        script
+    }};
     ($($count:expr, { $($body:tt)* })+) => {{
         let mut script = Vec::new();
         $(
             for _ in 0..$count {
-                script.extend(bsv_script! { $($body)* });
+                script.extend($crate::bsv_script! { $($body)* });
             }
         )+
         script
     }};
 }
 
-/// Parser stub for script to elements.
-pub fn parse_script(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
-    Ok((input, vec![]))
+/// Rejects index loops of more than [`MAX_UNROLL`] iterations, at compile
+/// time.
+#[doc(hidden)]
+pub const fn __check_unroll(start: i128, end: i128) {
+    if end - start > MAX_UNROLL as i128 {
+        panic!("loop_unroll!: too many iterations");
+    }
+}
+
+/// OP_XPICK_n: Copies the nth item (0 = top) to the top.
+/// Expands to [<n>, OP_PICK].
+#[macro_export]
+macro_rules! xpick {
+    ($n:expr) => {
+        $crate::bsv_script! { $n, OP_PICK }
+    };
+}
+
+/// OP_XDUP_n: Duplicates the top n items, keeping their order.
+/// Expands to n copies of [<n-1>, OP_PICK].
+#[macro_export]
+macro_rules! xdup {
+    ($n:expr) => {{
+        let n = $n;
+        let mut script = Vec::new();
+        for _ in 0..n {
+            script.extend($crate::bsv_script! { n - 1, OP_PICK });
+        }
+        script
+    }};
+}
+
+/// Reverses the bytes of an n-byte top item, e.g. to flip endianness.
+/// Expands to n-1 copies of [1, OP_SPLIT], then n-1 copies of
+/// [OP_SWAP, OP_CAT].
+#[macro_export]
+macro_rules! reverse_bytes {
+    ($n:expr) => {{
+        let n = $n;
+        let mut script = Vec::new();
+        for _ in 1..n {
+            script.extend($crate::bsv_script! { 1, OP_SPLIT });
+        }
+        for _ in 1..n {
+            script.extend($crate::bsv_script! { OP_SWAP, OP_CAT });
+        }
+        script
+    }};
+}
+
+/// Converts a number in 0..2^(8n) to exactly n unsigned little-endian bytes.
+/// Expands to [<n+1>, OP_NUM2BIN, <n>, OP_SPLIT, OP_DROP].
+#[macro_export]
+macro_rules! num2bin_le {
+    ($n:expr) => {
+        $crate::bsv_script! { $n + 1, OP_NUM2BIN, $n, OP_SPLIT, OP_DROP }
+    };
+}
+
+/// Drops the top n items.
+/// Expands to n copies of [OP_DROP].
+#[macro_export]
+macro_rules! drop_n {
+    ($n:expr) => {
+        $crate::loop_unroll!($n, { OP_DROP })
+    };
+}
+
+/// Moves the top n items to the alt stack; the top item ends up deepest.
+/// Expands to n copies of [OP_TOALTSTACK].
+#[macro_export]
+macro_rules! alt_push_n {
+    ($n:expr) => {
+        $crate::loop_unroll!($n, { OP_TOALTSTACK })
+    };
+}
+
+/// Runs body once for each of the first n bytes of the top item, in order.
+/// The body sees the byte on top of the stack as it stood below the item;
+/// the rest of the item waits on the alt stack and is dropped at the end.
+/// Fails at run time if the item is shorter than n bytes.
+/// Expands to n copies of [1, OP_SPLIT, OP_TOALTSTACK, body, OP_FROMALTSTACK],
+/// then [OP_DROP].
+#[macro_export]
+macro_rules! for_each_byte {
+    ($n:expr, { $($body:tt)* }) => {{
+        let mut script = Vec::new();
+        for _ in 0..$n {
+            script.extend($crate::bsv_script! { 1, OP_SPLIT, OP_TOALTSTACK });
+            script.extend($crate::bsv_script! { $($body)* });
+            script.extend($crate::bsv_script! { OP_FROMALTSTACK });
+        }
+        script.extend($crate::bsv_script! { OP_DROP });
+        script
+    }};
+}
+
+/// Fails unless the top item equals the given `bsv_script!` item, which is
+/// compared byte for byte and consumed along with it.
+/// Expands to [<value>, OP_EQUALVERIFY].
+#[macro_export]
+macro_rules! assert_eq_verify {
+    ($($value:tt)+) => {
+        $crate::bsv_script! { $($value)+, OP_EQUALVERIFY }
+    };
 }
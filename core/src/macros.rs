@@ -0,0 +1,262 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{MAX_UNROLL, MacroError, MacroErrorKind, ScriptNum};
+
+/// Integer expression over the enclosing macro's parameters, evaluated at
+/// expansion time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MacroArg {
+    /// A fixed number.
+    Const(i32),
+    /// The enclosing macro's parameter at this index.
+    Param(usize),
+    Add(Box<MacroArg>, Box<MacroArg>),
+    Sub(Box<MacroArg>, Box<MacroArg>),
+    Mul(Box<MacroArg>, Box<MacroArg>),
+    Neg(Box<MacroArg>),
+}
+
+impl MacroArg {
+    /// Value of the expression for the given arguments.
+    pub fn eval(&self, args: &[i32]) -> Result<i32, MacroErrorKind> {
+        let overflow = MacroErrorKind::ArithmeticOverflow;
+        match self {
+            Self::Const(n) => Ok(*n),
+            Self::Param(idx) => args
+                .get(*idx)
+                .copied()
+                .ok_or(MacroErrorKind::ParamOutOfRange),
+            Self::Add(a, b) => a.eval(args)?.checked_add(b.eval(args)?).ok_or(overflow),
+            Self::Sub(a, b) => a.eval(args)?.checked_sub(b.eval(args)?).ok_or(overflow),
+            Self::Mul(a, b) => a.eval(args)?.checked_mul(b.eval(args)?).ok_or(overflow),
+            Self::Neg(a) => a.eval(args)?.checked_neg().ok_or(overflow),
+        }
+    }
+}
+
+/// Macro element: opcode, parameter or reference to another macro.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MacroElem {
+    Op(u8),
+    /// Pushes the argument at this index as a script number.
+    Param(usize),
+    /// Pushes the value of an expression as a script number.
+    Push(MacroArg),
+    /// Expands the named macro from the registry with the given arguments.
+    Macro(String, Vec<MacroArg>),
+    /// Emits the elements as many times as the expression's value; not at
+    /// all when it is zero or negative. Counts above [`MAX_UNROLL`]
+    /// fail with [`MacroErrorKind::RepeatLimit`].
+    Repeat(MacroArg, Vec<MacroElem>),
+}
+
+/// Declared stack effect of a macro: how many items its expansion consumes
+/// from the top of the main stack and how many it leaves in their place.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct StackEffect {
+    pub inputs: MacroArg,
+    pub outputs: MacroArg,
+}
+
+impl StackEffect {
+    /// Effect independent of the macro's parameters.
+    pub fn fixed(inputs: i32, outputs: i32) -> Self {
+        Self {
+            inputs: MacroArg::Const(inputs),
+            outputs: MacroArg::Const(outputs),
+        }
+    }
+
+    /// Input and output counts for the given arguments.
+    pub fn eval(&self, args: &[i32]) -> Result<(i32, i32), MacroErrorKind> {
+        Ok((self.inputs.eval(args)?, self.outputs.eval(args)?))
+    }
+}
+
+/// Macro definition: (name, params, template) per article, with an optional
+/// stack effect that `nprint-verification` checks the template against.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MacroDef {
+    pub name: String,
+    pub param_count: usize,
+    pub template: Vec<MacroElem>,
+    pub effect: Option<StackEffect>,
+}
+
+/// Named macro definitions that may reference one another through
+/// [`MacroElem::Macro`].
+///
+/// ```
+/// use nprint_core::{MacroArg, MacroDef, MacroElem, MacroRegistry};
+/// use sv::script::op_codes::{OP_DROP, OP_ROLL};
+///
+/// let mut registry = MacroRegistry::new();
+/// registry.insert(MacroDef {
+///     name: "xroll".into(),
+///     param_count: 1,
+///     template: vec![MacroElem::Param(0), MacroElem::Op(OP_ROLL)],
+///     effect: None,
+/// });
+/// registry.insert(MacroDef {
+///     name: "xdrop".into(),
+///     param_count: 1,
+///     template: vec![
+///         MacroElem::Macro("xroll".into(), vec![MacroArg::Param(0)]),
+///         MacroElem::Op(OP_DROP),
+///     ],
+///     effect: None,
+/// });
+/// assert_eq!(registry.expand("xdrop", &[2]), Ok(vec![0x52, OP_ROLL, OP_DROP]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MacroRegistry {
+    defs: BTreeMap<String, MacroDef>,
+    recursion_limit: usize,
+}
+
+impl MacroRegistry {
+    /// Nesting depth allowed unless overridden with
+    /// [`MacroRegistry::with_recursion_limit`].
+    pub const DEFAULT_RECURSION_LIMIT: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            defs: BTreeMap::new(),
+            recursion_limit: Self::DEFAULT_RECURSION_LIMIT,
+        }
+    }
+
+    /// Allows at most `limit` macros to be in expansion at once, counting the
+    /// outermost one.
+    pub fn with_recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Adds `def` under its name, returning the definition it replaces.
+    pub fn insert(&mut self, def: MacroDef) -> Option<MacroDef> {
+        self.defs.insert(def.name.clone(), def)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MacroDef> {
+        self.defs.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.defs.contains_key(name)
+    }
+
+    /// Definitions in name order.
+    pub fn iter(&self) -> impl Iterator<Item = &MacroDef> {
+        self.defs.values()
+    }
+
+    /// Expands the registered macro `name`.
+    pub fn expand(&self, name: &str, args: &[i32]) -> Result<Vec<u8>, MacroError> {
+        let def = self.get(name).ok_or_else(|| MacroError {
+            kind: MacroErrorKind::UnknownMacro,
+            name: name.into(),
+        })?;
+        self.expand_def(def, args)
+    }
+
+    /// Expands `def`, which need not be registered, resolving its macro
+    /// references against this registry.
+    pub fn expand_def(&self, def: &MacroDef, args: &[i32]) -> Result<Vec<u8>, MacroError> {
+        let mut script = Vec::new();
+        self.expand_into(def, args, &mut Vec::new(), &mut script)?;
+        Ok(script)
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        def: &'a MacroDef,
+        args: &[i32],
+        active: &mut Vec<&'a str>,
+        script: &mut Vec<u8>,
+    ) -> Result<(), MacroError> {
+        let fail = |kind| MacroError {
+            kind,
+            name: def.name.clone(),
+        };
+        if args.len() != def.param_count {
+            return Err(fail(MacroErrorKind::ArgCountMismatch));
+        }
+        if active.contains(&def.name.as_str()) {
+            return Err(fail(MacroErrorKind::Cycle));
+        }
+        if active.len() >= self.recursion_limit {
+            return Err(fail(MacroErrorKind::RecursionLimit));
+        }
+        active.push(&def.name);
+        self.expand_elems(def, &def.template, args, active, script)?;
+        active.pop();
+        Ok(())
+    }
+
+    fn expand_elems<'a>(
+        &'a self,
+        def: &MacroDef,
+        elems: &[MacroElem],
+        args: &[i32],
+        active: &mut Vec<&'a str>,
+        script: &mut Vec<u8>,
+    ) -> Result<(), MacroError> {
+        let fail = |kind| MacroError {
+            kind,
+            name: def.name.clone(),
+        };
+        for elem in elems {
+            match elem {
+                MacroElem::Op(op) => script.push(*op),
+                MacroElem::Param(idx) => {
+                    let n = MacroArg::Param(*idx).eval(args).map_err(fail)?;
+                    script.extend(ScriptNum::from(n).to_push());
+                }
+                MacroElem::Push(expr) => {
+                    script.extend(ScriptNum::from(expr.eval(args).map_err(fail)?).to_push());
+                }
+                MacroElem::Macro(name, call_args) => {
+                    let callee = self.get(name).ok_or_else(|| MacroError {
+                        kind: MacroErrorKind::UnknownMacro,
+                        name: name.clone(),
+                    })?;
+                    let call_args = call_args
+                        .iter()
+                        .map(|a| a.eval(args))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(fail)?;
+                    self.expand_into(callee, &call_args, active, script)?;
+                }
+                MacroElem::Repeat(count, body) => {
+                    let count = count.eval(args).map_err(fail)?;
+                    if count > MAX_UNROLL as i32 {
+                        return Err(fail(MacroErrorKind::RepeatLimit));
+                    }
+                    for _ in 0..count {
+                        self.expand_elems(def, body, args, active, script)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for MacroRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Expand macro hygienically.
+///
+/// Without a registry, a [`MacroElem::Macro`] reference fails with
+/// [`MacroErrorKind::UnknownMacro`]; use [`MacroRegistry::expand_def`] to
+/// resolve them.
+pub fn expand_macro(def: &MacroDef, args: &[i32]) -> Result<Vec<u8>, MacroError> {
+    MacroRegistry::new().expand_def(def, args)
+}
@@ -0,0 +1,349 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use crate::{
+    MacroArg, MacroDef, MacroElem, MacroParseError, MacroParseErrorKind, MacroRegistry,
+    StackEffect, opcode_from_name,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token<'a> {
+    /// Run of letters, digits and underscores: a name, opcode or number.
+    Word(&'a str),
+    Punct(char),
+    /// `->` between the input and output counts of a stack effect.
+    Arrow,
+}
+
+/// Splits `text` into tokens and their byte offsets, dropping whitespace and
+/// `#` comments.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token<'_>)>, MacroParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '#' => while chars.next_if(|(_, c)| *c != '\n').is_some() {},
+            c if c.is_whitespace() => {}
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut end = offset + 1;
+                while let Some((i, _)) =
+                    chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    end = i + 1;
+                }
+                tokens.push((offset, Token::Word(&text[offset..end])));
+            }
+            '-' if chars.next_if(|(_, c)| *c == '>').is_some() => {
+                tokens.push((offset, Token::Arrow))
+            }
+            '(' | ')' | '{' | '}' | ',' | ':' | '$' | '+' | '-' | '*' => {
+                tokens.push((offset, Token::Punct(c)))
+            }
+            _ => return Err(error_at(text, offset, MacroParseErrorKind::UnexpectedToken)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn error_at(text: &str, offset: usize, kind: MacroParseErrorKind) -> MacroParseError {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    MacroParseError {
+        kind,
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+fn is_number(word: &str) -> bool {
+    word.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_name(word: &str) -> bool {
+    !word.starts_with(|c: char| c.is_ascii_digit())
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).map(|(_, t)| *t)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token<'a>), MacroParseError> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token.ok_or_else(|| self.error(self.text.len(), MacroParseErrorKind::UnexpectedEnd))
+    }
+
+    fn error(&self, offset: usize, kind: MacroParseErrorKind) -> MacroParseError {
+        error_at(self.text, offset, kind)
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), MacroParseError> {
+        match self.next()? {
+            (_, Token::Punct(c)) if c == punct => Ok(()),
+            (offset, _) => Err(self.error(offset, MacroParseErrorKind::UnexpectedToken)),
+        }
+    }
+
+    fn name(&mut self) -> Result<(usize, &'a str), MacroParseError> {
+        match self.next()? {
+            (offset, Token::Word(word)) if is_name(word) => Ok((offset, word)),
+            (offset, _) => Err(self.error(offset, MacroParseErrorKind::UnexpectedToken)),
+        }
+    }
+
+    fn number(&self, offset: usize, digits: &str, negative: bool) -> Result<i32, MacroParseError> {
+        let n = digits
+            .parse::<i64>()
+            .ok()
+            .map(|n| if negative { -n } else { n })
+            .and_then(|n| i32::try_from(n).ok());
+        n.ok_or_else(|| self.error(offset, MacroParseErrorKind::InvalidNumber))
+    }
+
+    /// `$name`, after the `$`.
+    fn param(&mut self, params: &[&str]) -> Result<usize, MacroParseError> {
+        let (offset, name) = self.name()?;
+        params
+            .iter()
+            .position(|p| *p == name)
+            .ok_or_else(|| self.error(offset, MacroParseErrorKind::UnknownParam))
+    }
+
+    /// macro NAME ( [PARAM {, PARAM}] ) [: EXPR -> EXPR] { BODY }
+    fn definition(&mut self) -> Result<MacroDef, MacroParseError> {
+        match self.next()? {
+            (_, Token::Word("macro")) => {}
+            (offset, _) => return Err(self.error(offset, MacroParseErrorKind::UnexpectedToken)),
+        }
+        let (_, name) = self.name()?;
+        self.expect('(')?;
+        let mut params = Vec::new();
+        if self.peek() != Some(Token::Punct(')')) {
+            loop {
+                let (offset, param) = self.name()?;
+                if params.contains(&param) {
+                    return Err(self.error(offset, MacroParseErrorKind::DuplicateParam));
+                }
+                params.push(param);
+                if self.peek() != Some(Token::Punct(',')) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        self.expect(')')?;
+        let mut effect = None;
+        if self.peek() == Some(Token::Punct(':')) {
+            self.pos += 1;
+            let inputs = self.expr(&params)?;
+            match self.next()? {
+                (_, Token::Arrow) => {}
+                (offset, _) => return Err(self.error(offset, MacroParseErrorKind::UnexpectedToken)),
+            }
+            let outputs = self.expr(&params)?;
+            effect = Some(StackEffect { inputs, outputs });
+        }
+        self.expect('{')?;
+        let template = self.body(&params)?;
+        Ok(MacroDef {
+            name: name.into(),
+            param_count: params.len(),
+            template,
+            effect,
+        })
+    }
+
+    /// Body elements up to and including the closing `}`.
+    fn body(&mut self, params: &[&str]) -> Result<Vec<MacroElem>, MacroParseError> {
+        let mut template = Vec::new();
+        loop {
+            let elem = match self.next()? {
+                (_, Token::Punct('}')) => return Ok(template),
+                (_, Token::Punct('$')) if self.peek() == Some(Token::Punct('(')) => {
+                    self.pos += 1;
+                    let expr = self.expr(params)?;
+                    self.expect(')')?;
+                    MacroElem::Push(expr)
+                }
+                (_, Token::Punct('$')) => MacroElem::Param(self.param(params)?),
+                (offset, Token::Punct('-')) => match self.next()? {
+                    (_, Token::Word(digits)) if is_number(digits) => {
+                        MacroElem::Push(MacroArg::Const(self.number(offset, digits, true)?))
+                    }
+                    (offset, _) => {
+                        return Err(self.error(offset, MacroParseErrorKind::UnexpectedToken));
+                    }
+                },
+                (offset, Token::Word(digits)) if is_number(digits) => {
+                    MacroElem::Push(MacroArg::Const(self.number(offset, digits, false)?))
+                }
+                (_, Token::Word("repeat")) if self.peek() == Some(Token::Punct('(')) => {
+                    self.pos += 1;
+                    let count = self.expr(params)?;
+                    self.expect(')')?;
+                    self.expect('{')?;
+                    MacroElem::Repeat(count, self.body(params)?)
+                }
+                (_, Token::Word(callee)) if self.peek() == Some(Token::Punct('(')) => {
+                    self.pos += 1;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(Token::Punct(')')) {
+                        loop {
+                            args.push(self.expr(params)?);
+                            if self.peek() != Some(Token::Punct(',')) {
+                                break;
+                            }
+                            self.pos += 1;
+                        }
+                    }
+                    self.expect(')')?;
+                    MacroElem::Macro(callee.into(), args)
+                }
+                (offset, Token::Word(word)) => MacroElem::Op(
+                    opcode_from_name(word)
+                        .ok_or_else(|| self.error(offset, MacroParseErrorKind::UnknownOpcode))?,
+                ),
+                (offset, _) => return Err(self.error(offset, MacroParseErrorKind::UnexpectedToken)),
+            };
+            template.push(elem);
+        }
+    }
+
+    /// TERM {(+|-) TERM}
+    fn expr(&mut self, params: &[&str]) -> Result<MacroArg, MacroParseError> {
+        let mut expr = self.term(params)?;
+        loop {
+            let op: fn(_, _) -> _ = match self.peek() {
+                Some(Token::Punct('+')) => MacroArg::Add,
+                Some(Token::Punct('-')) => MacroArg::Sub,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = op(Box::new(expr), Box::new(self.term(params)?));
+        }
+    }
+
+    /// FACTOR {* FACTOR}
+    fn term(&mut self, params: &[&str]) -> Result<MacroArg, MacroParseError> {
+        let mut term = self.factor(params)?;
+        while self.peek() == Some(Token::Punct('*')) {
+            self.pos += 1;
+            term = MacroArg::Mul(Box::new(term), Box::new(self.factor(params)?));
+        }
+        Ok(term)
+    }
+
+    /// NUMBER | $PARAM | -FACTOR | ( EXPR )
+    fn factor(&mut self, params: &[&str]) -> Result<MacroArg, MacroParseError> {
+        match self.next()? {
+            (offset, Token::Word(digits)) if is_number(digits) => {
+                Ok(MacroArg::Const(self.number(offset, digits, false)?))
+            }
+            (_, Token::Punct('$')) => Ok(MacroArg::Param(self.param(params)?)),
+            (_, Token::Punct('-')) => Ok(MacroArg::Neg(Box::new(self.factor(params)?))),
+            (_, Token::Punct('(')) => {
+                let expr = self.expr(params)?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            (offset, _) => Err(self.error(offset, MacroParseErrorKind::UnexpectedToken)),
+        }
+    }
+}
+
+/// Parses macro definitions in the `.nmacro` text format.
+///
+/// Each definition is `macro NAME(PARAMS) { BODY }`, optionally declaring
+/// its stack effect as `macro NAME(PARAMS): INPUTS -> OUTPUTS { BODY }`
+/// with both counts given as expressions. The body holds opcode
+/// names with or without the `OP_` prefix, decimal numbers, `$param` to
+/// push an argument, `$(expr)` to push an expression over the parameters
+/// built from numbers, `$param`, `+`, `-`, `*` and parentheses,
+/// `name(expr, ...)` to expand another macro and `repeat(expr) { BODY }` to
+/// emit a nested body that many times. `#` starts a comment running
+/// to the end of the line. Expressions are evaluated at expansion time, and
+/// macro references are resolved then too, so definitions may refer to
+/// macros defined later or in other files.
+///
+/// ```
+/// use nprint_core::{expand_macro, parse_macros};
+/// use sv::script::op_codes::{OP_1SUB, OP_ROLL};
+///
+/// let defs = parse_macros("
+///     ## Swap the top item with the nth
+///     macro xswap(n): $n -> $n { $n 1SUB ROLL }
+///     macro xswap_static(n) { $($n - 1) OP_ROLL }
+/// ").unwrap();
+/// assert_eq!(defs[0].name, "xswap");
+/// assert_eq!(defs[0].effect.as_ref().unwrap().eval(&[3]), Ok((3, 3)));
+/// assert_eq!(expand_macro(&defs[0], &[3]), Ok(vec![0x53, OP_1SUB, OP_ROLL]));
+/// assert_eq!(expand_macro(&defs[1], &[3]), Ok(vec![0x52, OP_ROLL]));
+/// ```
+pub fn parse_macros(text: &str) -> Result<Vec<MacroDef>, MacroParseError> {
+    let mut parser = Parser {
+        text,
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    let mut defs: Vec<MacroDef> = Vec::new();
+    while let Some(&(offset, _)) = parser.tokens.get(parser.pos) {
+        let def = parser.definition()?;
+        if defs.iter().any(|d| d.name == def.name) {
+            return Err(parser.error(offset, MacroParseErrorKind::DuplicateMacro));
+        }
+        defs.push(def);
+    }
+    Ok(defs)
+}
+
+impl MacroRegistry {
+    /// Adds every definition in `.nmacro` text (see [`parse_macros`]),
+    /// replacing registered macros of the same name. Nothing is added if the
+    /// text fails to parse.
+    pub fn load_str(&mut self, text: &str) -> Result<(), MacroParseError> {
+        for def in parse_macros(text)? {
+            self.insert(def);
+        }
+        Ok(())
+    }
+
+    /// Adds every definition in the `.nmacro` file at `path`. A parse failure
+    /// is returned as [`std::io::ErrorKind::InvalidData`] wrapping the
+    /// [`MacroParseError`].
+    #[cfg(feature = "std")]
+    pub fn load_file(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        self.load_str(&text)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Registry holding the standard macro library: `xswap`, `xdrop`, `xrot`,
+/// `hashcat`, `xpick`, `xdup`, `reverse_bytes`, `num2bin_le`, `drop_n`,
+/// `alt_push_n`, `for_each_byte` and `assert_eq_verify`, each expanding to
+/// the same script as the `macro_rules!` macro of that name given numeric
+/// arguments. `assert_eq_verify` compares against a number only, where the
+/// `macro_rules!` form takes any `bsv_script!` item. `for_each_byte` calls an
+/// `each_byte` macro for its body, which the caller registers, and is the one
+/// definition without a declared stack effect.
+///
+/// ```
+/// use nprint_core::{std_macros, xdup};
+///
+/// let registry = std_macros();
+/// assert_eq!(registry.expand("xdup", &[2]), Ok(xdup!(2)));
+/// ```
+pub fn std_macros() -> MacroRegistry {
+    let mut registry = MacroRegistry::new();
+    registry
+        .load_str(include_str!("std.nmacro"))
+        .expect("std.nmacro parses");
+    registry
+}
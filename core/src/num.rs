@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Add, Mul, Neg, Sub};
+use num_bigint::{BigInt, Sign};
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::{ScriptErrorKind, encode_push};
+
+/// Arbitrary-precision script number (little-endian sign-magnitude on the stack).
+///
+/// ```
+/// use nprint_core::ScriptNum;
+///
+/// let big = ScriptNum::from(i128::MAX);
+/// let bytes = big.encode();
+/// assert_eq!(bytes.len(), 16);
+/// assert_eq!(ScriptNum::decode(&bytes, 16, true), Ok(big));
+/// assert_eq!(ScriptNum::from(-1).encode(), vec![0x81]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ScriptNum(BigInt);
+
+impl ScriptNum {
+    /// Decodes a stack element as a number.
+    ///
+    /// Operands longer than `max_len` bytes are rejected, as are non-minimal
+    /// encodings (superfluous zero padding) when `require_minimal` is set.
+    pub fn decode(
+        bytes: &[u8],
+        max_len: usize,
+        require_minimal: bool,
+    ) -> Result<Self, ScriptErrorKind> {
+        if bytes.len() > max_len {
+            return Err(ScriptErrorKind::NumberOverflow);
+        }
+        if require_minimal && !is_minimal(bytes) {
+            return Err(ScriptErrorKind::NonMinimalNumber);
+        }
+        let Some((&last, _)) = bytes.split_last() else {
+            return Ok(Self::default());
+        };
+        let mut magnitude = bytes.to_vec();
+        *magnitude.last_mut().unwrap() = last & 0x7f;
+        let sign = if last & 0x80 != 0 {
+            Sign::Minus
+        } else {
+            Sign::Plus
+        };
+        Ok(Self(BigInt::from_bytes_le(sign, &magnitude)))
+    }
+
+    /// Minimal encoding; zero is the empty vector.
+    pub fn encode(&self) -> Vec<u8> {
+        if self.0.is_zero() {
+            return Vec::new();
+        }
+        let (sign, mut bytes) = self.0.to_bytes_le();
+        let negative = sign == Sign::Minus;
+        let last = bytes.last_mut().unwrap();
+        if *last & 0x80 != 0 {
+            bytes.push(if negative { 0x80 } else { 0x00 });
+        } else if negative {
+            *last |= 0x80;
+        }
+        bytes
+    }
+
+    /// Script fragment pushing this number: OP_0, OP_1NEGATE, OP_1 to OP_16,
+    /// or the smallest data push holding its encoding.
+    pub fn to_push(&self) -> Vec<u8> {
+        encode_push(&self.encode(), true)
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn abs(&self) -> Self {
+        Self(self.0.abs())
+    }
+
+    /// Truncating division; `None` for a zero divisor.
+    pub fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        (!rhs.is_zero()).then(|| Self(&self.0 / &rhs.0))
+    }
+
+    /// Remainder taking the sign of the dividend; `None` for a zero divisor.
+    pub fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+        (!rhs.is_zero()).then(|| Self(&self.0 % &rhs.0))
+    }
+}
+
+/// Returns true if `bytes` carries no superfluous zero padding.
+pub(crate) fn is_minimal(bytes: &[u8]) -> bool {
+    match bytes {
+        [] => true,
+        [.., last] if last & 0x7f != 0 => true,
+        [_] => false,
+        [.., prev, _] => prev & 0x80 != 0,
+    }
+}
+
+macro_rules! impl_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for ScriptNum {
+                fn from(n: $t) -> Self {
+                    Self(BigInt::from(n))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_int!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+impl From<bool> for ScriptNum {
+    fn from(b: bool) -> Self {
+        Self::from(b as u8)
+    }
+}
+
+impl From<BigInt> for ScriptNum {
+    fn from(n: BigInt) -> Self {
+        Self(n)
+    }
+}
+
+impl From<ScriptNum> for BigInt {
+    fn from(n: ScriptNum) -> Self {
+        n.0
+    }
+}
+
+impl Add for ScriptNum {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0 + rhs.0)
+    }
+}
+
+impl Sub for ScriptNum {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0 - rhs.0)
+    }
+}
+
+impl Mul for ScriptNum {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self(self.0 * rhs.0)
+    }
+}
+
+impl Neg for ScriptNum {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl fmt::Display for ScriptNum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
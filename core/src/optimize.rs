@@ -0,0 +1,226 @@
+use alloc::vec::Vec;
+use sv::script::op_codes::{
+    OP_0, OP_1, OP_1ADD, OP_1NEGATE, OP_1SUB, OP_2, OP_2DROP, OP_2DUP, OP_ADD, OP_CHECKMULTISIG,
+    OP_CHECKMULTISIGVERIFY, OP_CHECKSIG, OP_CHECKSIGVERIFY, OP_DROP, OP_DUP, OP_EQUAL,
+    OP_EQUALVERIFY, OP_FROMALTSTACK, OP_IF, OP_NEGATE, OP_NOP, OP_NOT, OP_NOTIF, OP_NUMEQUAL,
+    OP_NUMEQUALVERIFY, OP_OVER, OP_PICK, OP_RETURN, OP_ROLL, OP_ROT, OP_SUB, OP_SWAP,
+    OP_TOALTSTACK, OP_VERIFY,
+};
+
+use crate::{EquivalenceError, Instruction, Instructions, ScriptNum, Stack};
+
+/// How far [`optimize_with`] may go in changing a script.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum OptLevel {
+    /// Leaves the script untouched.
+    None,
+    /// Rewrites that keep the outcome on every input stack: the same final
+    /// stacks on success, and failure wherever the original fails.
+    #[default]
+    Basic,
+    /// Also deletes sequences that only fail on a short stack or a
+    /// non-numeric operand. The result agrees with the original wherever the
+    /// original succeeds, but may succeed where it fails, so a locking script
+    /// can accept more unlocking scripts than before.
+    Aggressive,
+}
+
+/// Peephole rule: replace the opcode sequence `pattern` with `replacement`
+/// at optimization levels of `level` and above.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub pattern: &'static [u8],
+    pub replacement: &'static [u8],
+    pub level: OptLevel,
+}
+
+const fn rule(pattern: &'static [u8], replacement: &'static [u8], level: OptLevel) -> Rule {
+    Rule {
+        pattern,
+        replacement,
+        level,
+    }
+}
+
+/// Rules applied by [`optimize_with`], first match wins.
+///
+/// Removing or fusing opcodes lowers the script's opcode count, so an
+/// optimized script may stay under `max_ops` where the original did not.
+pub const RULES: &[Rule] = &[
+    rule(&[OP_0, OP_PICK], &[OP_DUP], OptLevel::Basic),
+    rule(&[OP_1, OP_PICK], &[OP_OVER], OptLevel::Basic),
+    rule(&[OP_1, OP_ROLL], &[OP_SWAP], OptLevel::Basic),
+    rule(&[OP_2, OP_ROLL], &[OP_ROT], OptLevel::Basic),
+    rule(&[OP_EQUAL, OP_VERIFY], &[OP_EQUALVERIFY], OptLevel::Basic),
+    rule(
+        &[OP_NUMEQUAL, OP_VERIFY],
+        &[OP_NUMEQUALVERIFY],
+        OptLevel::Basic,
+    ),
+    rule(
+        &[OP_CHECKSIG, OP_VERIFY],
+        &[OP_CHECKSIGVERIFY],
+        OptLevel::Basic,
+    ),
+    rule(
+        &[OP_CHECKMULTISIG, OP_VERIFY],
+        &[OP_CHECKMULTISIGVERIFY],
+        OptLevel::Basic,
+    ),
+    rule(&[OP_1, OP_ADD], &[OP_1ADD], OptLevel::Basic),
+    rule(&[OP_1, OP_SUB], &[OP_1SUB], OptLevel::Basic),
+    rule(&[OP_1, OP_NEGATE], &[OP_1NEGATE], OptLevel::Basic),
+    rule(&[OP_DROP, OP_DROP], &[OP_2DROP], OptLevel::Basic),
+    rule(&[OP_OVER, OP_OVER], &[OP_2DUP], OptLevel::Basic),
+    rule(&[OP_NOP], &[], OptLevel::Basic),
+    // OP_NOT fails on operands that are not numbers; the branch opcodes
+    // accept any value
+    rule(&[OP_NOT, OP_IF], &[OP_NOTIF], OptLevel::Aggressive),
+    rule(&[OP_NOT, OP_NOTIF], &[OP_IF], OptLevel::Aggressive),
+    rule(&[OP_0, OP_ROLL], &[], OptLevel::Aggressive),
+    rule(&[OP_DUP, OP_DROP], &[], OptLevel::Aggressive),
+    rule(&[OP_SWAP, OP_SWAP], &[], OptLevel::Aggressive),
+    rule(&[OP_ROT, OP_ROT, OP_ROT], &[], OptLevel::Aggressive),
+    rule(&[OP_TOALTSTACK, OP_FROMALTSTACK], &[], OptLevel::Aggressive),
+];
+
+/// Optimizes `script` at [`OptLevel::Basic`].
+///
+/// ```
+/// use nprint_core::{bsv_script, optimize};
+///
+/// let script = bsv_script! { OP_1, OP_ROLL, OP_EQUAL, OP_VERIFY };
+/// assert_eq!(optimize(&script), bsv_script! { OP_SWAP, OP_EQUALVERIFY });
+/// ```
+pub fn optimize(script: &[u8]) -> Vec<u8> {
+    optimize_with(script, OptLevel::Basic)
+}
+
+/// Applies the [`RULES`] up to `level` until none matches.
+///
+/// Rules match whole single-byte instructions, never bytes inside push
+/// data. Everything from the first OP_RETURN on is copied unchanged, as is a
+/// truncated push at the end.
+pub fn optimize_with(script: &[u8], level: OptLevel) -> Vec<u8> {
+    let mut out: Vec<&[u8]> = Vec::new();
+    let mut tail = script.len();
+    for (offset, instruction) in Instructions::new(script) {
+        if instruction == Instruction::Op(OP_RETURN) {
+            tail = offset;
+            break;
+        }
+        out.push(&script[offset..offset + instruction.len()]);
+        reduce(&mut out, level);
+    }
+    let mut optimized: Vec<u8> = out.concat();
+    optimized.extend_from_slice(&script[tail..]);
+    optimized
+}
+
+/// Rewrites the end of `out` until no rule matches there. Everything before
+/// the last instruction is already reduced, so only suffixes need checking.
+fn reduce(out: &mut Vec<&[u8]>, level: OptLevel) {
+    'rewrite: loop {
+        for rule in RULES.iter().filter(|rule| rule.level <= level) {
+            let Some(start) = out.len().checked_sub(rule.pattern.len()) else {
+                continue;
+            };
+            let matches = out[start..]
+                .iter()
+                .zip(rule.pattern)
+                .all(|(instruction, op)| *instruction == [*op]);
+            if matches {
+                out.truncate(start);
+                out.extend(rule.replacement.chunks(1));
+                continue 'rewrite;
+            }
+        }
+        return;
+    }
+}
+
+/// Optimizes `script` and runs [`check_equivalence`] on the result with
+/// `trials` random stacks.
+pub fn optimize_checked(
+    script: &[u8],
+    level: OptLevel,
+    trials: usize,
+) -> Result<Vec<u8>, EquivalenceError> {
+    let optimized = optimize_with(script, level);
+    check_equivalence(script, &optimized, level, trials, 0)?;
+    Ok(optimized)
+}
+
+/// Proof by testing: executes both scripts on `trials` random input stacks
+/// drawn from `seed` and compares the outcomes.
+///
+/// At [`OptLevel::Basic`] both must fail on the same inputs and otherwise
+/// leave the same main and alt stacks; at [`OptLevel::Aggressive`] only
+/// inputs on which `original` succeeds are compared. Stacks are up to eight
+/// items of small numbers, their non-minimal and negative-zero forms, and
+/// short random byte strings, so equal operands are common.
+///
+/// ```
+/// use nprint_core::{OptLevel, bsv_script, check_equivalence};
+///
+/// let dup = bsv_script! { OP_0, OP_PICK };
+/// assert!(check_equivalence(&dup, &bsv_script! { OP_DUP }, OptLevel::Basic, 200, 1).is_ok());
+/// let err = check_equivalence(&dup, &bsv_script! { OP_OVER }, OptLevel::Basic, 200, 1);
+/// assert!(err.is_err());
+/// ```
+pub fn check_equivalence(
+    original: &[u8],
+    optimized: &[u8],
+    level: OptLevel,
+    trials: usize,
+    seed: u64,
+) -> Result<(), EquivalenceError> {
+    let mut rng = SplitMix64(seed);
+    for _ in 0..trials {
+        let inputs: Vec<Vec<u8>> = (0..rng.below(9)).map(|_| rng.item()).collect();
+        let run = |script: &[u8]| {
+            let mut stack = Stack {
+                main: inputs.clone(),
+                ..Default::default()
+            };
+            stack.execute(script).map(|()| (stack.main, stack.alt))
+        };
+        let agree = match (run(original), run(optimized)) {
+            (Ok(a), Ok(b)) => a == b,
+            (Err(_), Err(_)) => true,
+            (Err(_), Ok(_)) => level >= OptLevel::Aggressive,
+            (Ok(_), Err(_)) => false,
+        };
+        if !agree {
+            return Err(EquivalenceError { inputs });
+        }
+    }
+    Ok(())
+}
+
+/// Small deterministic generator for [`check_equivalence`]; any seed works.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn item(&mut self) -> Vec<u8> {
+        match self.below(8) {
+            0 => Vec::new(),
+            1 => alloc::vec![0x80],
+            2 => alloc::vec![self.below(3) as u8, 0x00],
+            3 => (0..1 + self.below(6)).map(|_| self.next() as u8).collect(),
+            _ => ScriptNum::from(self.below(20) as i64 - 2).encode(),
+        }
+    }
+}
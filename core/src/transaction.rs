@@ -0,0 +1,142 @@
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256};
+
+/// Sign all inputs and outputs.
+pub const SIGHASH_ALL: u8 = 0x01;
+/// Sign all inputs and no outputs.
+pub const SIGHASH_NONE: u8 = 0x02;
+/// Sign all inputs and the output at the same index as the signed input.
+pub const SIGHASH_SINGLE: u8 = 0x03;
+/// Replay-protected BSV signature hash; required on every signature.
+pub const SIGHASH_FORKID: u8 = 0x40;
+/// Sign only the input being spent.
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
+/// Reference to an output of a previous transaction.
+///
+/// `txid` is in internal (serialized) byte order, i.e. reversed from the hex
+/// shown by explorers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OutPoint {
+    pub txid: [u8; 32],
+    pub vout: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TxInput {
+    pub prevout: OutPoint,
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TxOutput {
+    /// Amount in satoshis.
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+/// Transaction fields covered by the signature hash.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Transaction {
+    pub version: u32,
+    pub inputs: Vec<TxInput>,
+    pub outputs: Vec<TxOutput>,
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// BSV signature hash (BIP143 digest with SIGHASH_FORKID) of input
+    /// `input_index`, spending `amount` satoshis locked by `script_code`.
+    ///
+    /// Panics if `input_index` is out of range.
+    pub fn sighash(
+        &self,
+        input_index: usize,
+        script_code: &[u8],
+        amount: u64,
+        sighash_type: u8,
+    ) -> [u8; 32] {
+        let base = sighash_type & 0x1f;
+        let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+        let input = &self.inputs[input_index];
+
+        let mut hash_prevouts = [0; 32];
+        if !anyone_can_pay {
+            let mut buf = Vec::new();
+            for txin in &self.inputs {
+                write_outpoint(&mut buf, &txin.prevout);
+            }
+            hash_prevouts = sha256d(&buf);
+        }
+
+        let mut hash_sequence = [0; 32];
+        if !anyone_can_pay && base != SIGHASH_SINGLE && base != SIGHASH_NONE {
+            let mut buf = Vec::new();
+            for txin in &self.inputs {
+                buf.extend(txin.sequence.to_le_bytes());
+            }
+            hash_sequence = sha256d(&buf);
+        }
+
+        let mut hash_outputs = [0; 32];
+        if base != SIGHASH_SINGLE && base != SIGHASH_NONE {
+            let mut buf = Vec::new();
+            for txout in &self.outputs {
+                write_output(&mut buf, txout);
+            }
+            hash_outputs = sha256d(&buf);
+        } else if base == SIGHASH_SINGLE && input_index < self.outputs.len() {
+            let mut buf = Vec::new();
+            write_output(&mut buf, &self.outputs[input_index]);
+            hash_outputs = sha256d(&buf);
+        }
+
+        let mut preimage = Vec::new();
+        preimage.extend(self.version.to_le_bytes());
+        preimage.extend(hash_prevouts);
+        preimage.extend(hash_sequence);
+        write_outpoint(&mut preimage, &input.prevout);
+        write_varint(&mut preimage, script_code.len() as u64);
+        preimage.extend_from_slice(script_code);
+        preimage.extend(amount.to_le_bytes());
+        preimage.extend(input.sequence.to_le_bytes());
+        preimage.extend(hash_outputs);
+        preimage.extend(self.lock_time.to_le_bytes());
+        preimage.extend((sighash_type as u32).to_le_bytes());
+        sha256d(&preimage)
+    }
+}
+
+fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+fn write_varint(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend((n as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend((n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend(n.to_le_bytes());
+        }
+    }
+}
+
+fn write_outpoint(buf: &mut Vec<u8>, outpoint: &OutPoint) {
+    buf.extend(outpoint.txid);
+    buf.extend(outpoint.vout.to_le_bytes());
+}
+
+fn write_output(buf: &mut Vec<u8>, output: &TxOutput) {
+    buf.extend(output.value.to_le_bytes());
+    write_varint(buf, output.script_pubkey.len() as u64);
+    buf.extend_from_slice(&output.script_pubkey);
+}
@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::AsmErrorKind::*;
+    use nprint_core::{AsmError, from_asm, opcode_from_name, opcode_name, to_asm};
+    use proptest::prelude::*;
+    use sv::script::op_codes::{
+        OP_0, OP_1, OP_1NEGATE, OP_16, OP_CHECKLOCKTIMEVERIFY, OP_CHECKSIG, OP_DUP, OP_EQUALVERIFY,
+        OP_HASH160, OP_NOP10, OP_PUSHDATA1, OP_PUSHDATA2, OP_PUSHDATA4,
+    };
+
+    fn err(asm: &str) -> AsmError {
+        from_asm(asm).unwrap_err()
+    }
+
+    proptest! {
+        #[test]
+        fn test_roundtrip(script in prop::collection::vec(any::<u8>(), 0..300)) {
+            prop_assert_eq!(from_asm(&to_asm(&script)), Ok(script));
+        }
+    }
+
+    #[test]
+    fn test_opcode_names() {
+        for op in 0..=u8::MAX {
+            if let Some(name) = opcode_name(op) {
+                assert_eq!(opcode_from_name(name), Some(op), "{name}");
+            }
+        }
+        // Every opcode other than a direct push length is named
+        let named = (0..=u8::MAX)
+            .filter(|op| opcode_name(*op).is_some())
+            .count();
+        assert_eq!(named, 1 + (OP_NOP10 - OP_PUSHDATA1 + 1) as usize);
+        assert_eq!(opcode_name(0x01), None);
+        assert_eq!(opcode_name(0xba), None);
+        assert_eq!(
+            opcode_name(OP_CHECKLOCKTIMEVERIFY),
+            Some("OP_CHECKLOCKTIMEVERIFY")
+        );
+        assert_eq!(opcode_from_name("NOP2"), Some(OP_CHECKLOCKTIMEVERIFY));
+        assert_eq!(opcode_from_name("OP_TRUE"), Some(OP_1));
+        assert_eq!(opcode_from_name("FALSE"), Some(OP_0));
+        assert_eq!(opcode_from_name("OP_UNKNOWN"), None);
+    }
+
+    #[test]
+    fn test_to_asm() {
+        let pkh = [0x11; 20];
+        let p2pkh = [
+            &[OP_DUP, OP_HASH160, 20][..],
+            &pkh,
+            &[OP_EQUALVERIFY, OP_CHECKSIG],
+        ]
+        .concat();
+        assert_eq!(
+            to_asm(&p2pkh),
+            format!(
+                "OP_DUP OP_HASH160 0x{} OP_EQUALVERIFY OP_CHECKSIG",
+                "11".repeat(20)
+            )
+        );
+        assert_eq!(to_asm(&[]), "");
+        assert_eq!(to_asm(&[OP_0, OP_1NEGATE, OP_16]), "OP_0 OP_1NEGATE OP_16");
+        // Push encodings are kept
+        assert_eq!(to_asm(&[OP_PUSHDATA1, 0]), "OP_PUSHDATA1 0x");
+        assert_eq!(to_asm(&[OP_PUSHDATA2, 1, 0, 0xab]), "OP_PUSHDATA2 0xab");
+        assert_eq!(
+            to_asm(&[OP_PUSHDATA4, 1, 0, 0, 0, 0xab]),
+            "OP_PUSHDATA4 0xab"
+        );
+        assert_eq!(to_asm(&[0xba, 0xff]), "OP_UNKNOWN186 OP_UNKNOWN255");
+        assert_eq!(to_asm(&[OP_DUP, OP_PUSHDATA1, 3, 1]), "OP_DUP [4c0301]");
+    }
+
+    #[test]
+    fn test_from_asm() {
+        assert_eq!(from_asm("  OP_DUP\n\tDUP  "), Ok(vec![OP_DUP, OP_DUP]));
+        assert_eq!(
+            from_asm("0 -1 16 17 -129"),
+            Ok(vec![OP_0, OP_1NEGATE, OP_16, 1, 17, 2, 0x81, 0x80])
+        );
+        assert_eq!(
+            from_asm("340282366920938463463374607431768211456"),
+            Ok([&[17][..], &[0; 16], &[1]].concat())
+        );
+        assert_eq!(from_asm("0x 0xABcd"), Ok(vec![OP_0, 2, 0xab, 0xcd]));
+        // Long hex gets the smallest OP_PUSHDATA
+        let long = format!("0x{}", "00".repeat(76));
+        assert_eq!(from_asm(&long).unwrap()[..2], [OP_PUSHDATA1, 76]);
+        let long = format!("0x{}", "00".repeat(256));
+        assert_eq!(from_asm(&long).unwrap()[..3], [OP_PUSHDATA2, 0, 1]);
+        assert_eq!(from_asm("[4c05] OP_1"), Ok(vec![OP_PUSHDATA1, 5, OP_1]));
+    }
+
+    #[test]
+    fn test_from_asm_errors() {
+        assert_eq!(
+            err("OP_DUP OP_FOO"),
+            AsmError {
+                kind: UnknownToken,
+                offset: 7
+            }
+        );
+        assert_eq!(err("1.5").kind, UnknownToken);
+        assert_eq!(err("OP_UNKNOWN7").kind, UnknownToken);
+        assert_eq!(err("OP_UNKNOWN300").kind, UnknownToken);
+        assert_eq!(err("0xabc").kind, InvalidHex);
+        assert_eq!(err("0x+a").kind, InvalidHex);
+        assert_eq!(err("[zz]").kind, InvalidHex);
+        assert_eq!(err("OP_PUSHDATA1").kind, MissingPushData);
+        assert_eq!(err("OP_PUSHDATA2 12").kind, MissingPushData);
+        assert_eq!(
+            err("OP_PUSHDATA4 0xgg"),
+            AsmError {
+                kind: InvalidHex,
+                offset: 13
+            }
+        );
+        let long = format!("OP_PUSHDATA1 0x{}", "00".repeat(256));
+        assert_eq!(err(&long).kind, PushTooLong);
+        assert_eq!(
+            err("OP_DUP OP_FOO").to_string(),
+            "Unknown token at offset 7"
+        );
+    }
+}
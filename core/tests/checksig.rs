@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+    use k256::ecdsa::{Signature, SigningKey};
+    use nprint_core::ScriptErrorKind::*;
+    use nprint_core::{
+        ExecutionContext, OutPoint, SIGHASH_ALL, SIGHASH_ANYONECANPAY, SIGHASH_FORKID,
+        SIGHASH_NONE, SIGHASH_SINGLE, ScriptFlags, Stack, Transaction, TxInput, TxOutput,
+    };
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+    use sv::script::op_codes::{
+        OP_1, OP_2, OP_3, OP_CHECKMULTISIG, OP_CHECKSIG, OP_CHECKSIGVERIFY, OP_CODESEPARATOR,
+        OP_DUP, OP_EQUALVERIFY, OP_HASH160,
+    };
+
+    const AMOUNT: u64 = 50_000;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_slice(&[seed; 32]).unwrap()
+    }
+
+    fn pubkey(key: &SigningKey) -> Vec<u8> {
+        key.verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    fn push(script: &mut Vec<u8>, data: &[u8]) {
+        script.push(data.len() as u8);
+        script.extend_from_slice(data);
+    }
+
+    fn push_of(data: &[u8]) -> Vec<u8> {
+        let mut script = Vec::new();
+        push(&mut script, data);
+        script
+    }
+
+    fn p2pkh(pubkey: &[u8]) -> Vec<u8> {
+        let pkh = Ripemd160::digest(Sha256::digest(pubkey));
+        let mut script = vec![OP_DUP, OP_HASH160];
+        push(&mut script, &pkh);
+        script.extend([OP_EQUALVERIFY, OP_CHECKSIG]);
+        script
+    }
+
+    fn spending_tx(inputs: usize) -> Transaction {
+        Transaction {
+            version: 1,
+            inputs: (0..inputs)
+                .map(|i| TxInput {
+                    prevout: OutPoint {
+                        txid: [i as u8 + 1; 32],
+                        vout: i as u32,
+                    },
+                    script_sig: vec![],
+                    sequence: 0xffff_ffff,
+                })
+                .collect(),
+            outputs: vec![
+                TxOutput {
+                    value: 30_000,
+                    script_pubkey: vec![OP_1],
+                },
+                TxOutput {
+                    value: 19_000,
+                    script_pubkey: vec![OP_2],
+                },
+            ],
+            lock_time: 0,
+        }
+    }
+
+    fn sign(key: &SigningKey, tx: &Transaction, index: usize, code: &[u8], ty: u8) -> Vec<u8> {
+        let hash = tx.sighash(index, code, AMOUNT, ty);
+        let sig: Signature = key.sign_prehash(&hash).unwrap();
+        let mut bytes = sig.to_der().as_bytes().to_vec();
+        bytes.push(ty);
+        bytes
+    }
+
+    fn verify(tx: &Transaction, index: usize, locking_script: &[u8]) -> bool {
+        let ctx = ExecutionContext {
+            tx,
+            input_index: index,
+            amount: AMOUNT,
+            locking_script,
+            flags: ScriptFlags::default(),
+        };
+        ctx.verify().unwrap()
+    }
+
+    #[test]
+    fn test_p2pkh_spend() {
+        let key = key(1);
+        let locking = p2pkh(&pubkey(&key));
+        let mut tx = spending_tx(1);
+        let sig = sign(&key, &tx, 0, &locking, SIGHASH_ALL | SIGHASH_FORKID);
+        push(&mut tx.inputs[0].script_sig, &sig);
+        push(&mut tx.inputs[0].script_sig, &pubkey(&key));
+        assert!(verify(&tx, 0, &locking));
+
+        let mut tampered = tx.clone();
+        tampered.outputs[0].value += 1;
+        assert!(!verify(&tampered, 0, &locking));
+
+        // The amount is committed to as well
+        let ctx = ExecutionContext {
+            tx: &tx,
+            input_index: 0,
+            amount: AMOUNT - 1,
+            locking_script: &locking,
+            flags: ScriptFlags::default(),
+        };
+        assert_eq!(ctx.verify(), Ok(false));
+    }
+
+    #[test]
+    fn test_sighash_types() {
+        let key = key(2);
+        let locking = [push_of(&pubkey(&key)), vec![OP_CHECKSIG]].concat();
+        let signed = |ty: u8, index: usize| {
+            let mut tx = spending_tx(2);
+            let sig = sign(&key, &tx, index, &locking, ty | SIGHASH_FORKID);
+            push(&mut tx.inputs[index].script_sig, &sig);
+            tx
+        };
+
+        // ALL commits to every output
+        let mut tx = signed(SIGHASH_ALL, 0);
+        assert!(verify(&tx, 0, &locking));
+        tx.outputs[1].value -= 1;
+        assert!(!verify(&tx, 0, &locking));
+
+        // NONE commits to no output
+        let mut tx = signed(SIGHASH_NONE, 0);
+        tx.outputs.clear();
+        assert!(verify(&tx, 0, &locking));
+
+        // SINGLE commits only to the output at the input's index
+        let mut tx = signed(SIGHASH_SINGLE, 1);
+        tx.outputs[0].value -= 1;
+        assert!(verify(&tx, 1, &locking));
+        tx.outputs[1].value -= 1;
+        assert!(!verify(&tx, 1, &locking));
+
+        // ANYONECANPAY lets other inputs change
+        let mut tx = signed(SIGHASH_ALL | SIGHASH_ANYONECANPAY, 0);
+        tx.inputs[1].prevout.vout = 7;
+        tx.inputs.push(TxInput::default());
+        assert!(verify(&tx, 0, &locking));
+
+        let mut tx = signed(SIGHASH_ALL, 0);
+        tx.inputs[1].sequence = 0;
+        assert!(!verify(&tx, 0, &locking));
+    }
+
+    #[test]
+    fn test_high_s_accepted() {
+        let key = key(3);
+        let locking = [push_of(&pubkey(&key)), vec![OP_CHECKSIG]].concat();
+        let mut tx = spending_tx(1);
+        let ty = SIGHASH_ALL | SIGHASH_FORKID;
+        let sig: Signature = key
+            .sign_prehash(&tx.sighash(0, &locking, AMOUNT, ty))
+            .unwrap();
+        let (r, s) = sig.split_scalars();
+        let high = Signature::from_scalars(r, -*s).unwrap();
+        assert!(high.normalize_s().is_some());
+        let mut bytes = high.to_der().as_bytes().to_vec();
+        bytes.push(ty);
+        push(&mut tx.inputs[0].script_sig, &bytes);
+        assert!(verify(&tx, 0, &locking));
+    }
+
+    #[test]
+    fn test_multisig() {
+        let keys = [key(4), key(5), key(6)];
+        let mut locking = vec![OP_2];
+        for key in &keys {
+            push(&mut locking, &pubkey(key));
+        }
+        locking.extend([OP_3, OP_CHECKMULTISIG]);
+        let ty = SIGHASH_ALL | SIGHASH_FORKID;
+        let unlock = |signers: &[usize]| {
+            let mut tx = spending_tx(1);
+            let sigs: Vec<_> = signers
+                .iter()
+                .map(|&i| sign(&keys[i], &tx, 0, &locking, ty))
+                .collect();
+            tx.inputs[0].script_sig.push(0);
+            for sig in &sigs {
+                push(&mut tx.inputs[0].script_sig, sig);
+            }
+            tx
+        };
+        assert!(verify(&unlock(&[0, 1]), 0, &locking));
+        assert!(verify(&unlock(&[0, 2]), 0, &locking));
+        assert!(verify(&unlock(&[1, 2]), 0, &locking));
+        // Signatures out of key order do not match
+        assert!(!verify(&unlock(&[1, 0]), 0, &locking));
+        assert!(!verify(&unlock(&[0, 0]), 0, &locking));
+    }
+
+    #[test]
+    fn test_codeseparator_script_code() {
+        // CODESEPARATOR <pk> CHECKSIGVERIFY CODESEPARATOR <pk> CHECKSIG
+        let key = key(7);
+        let mut locking = vec![OP_CODESEPARATOR];
+        locking.extend(push_of(&pubkey(&key)));
+        locking.push(OP_CHECKSIGVERIFY);
+        let second = locking.len() + 1;
+        locking.push(OP_CODESEPARATOR);
+        locking.extend(push_of(&pubkey(&key)));
+        locking.push(OP_CHECKSIG);
+
+        let ty = SIGHASH_ALL | SIGHASH_FORKID;
+        let unlock = |last_code: &[u8]| {
+            let mut tx = spending_tx(1);
+            let last = sign(&key, &tx, 0, last_code, ty);
+            let first = sign(&key, &tx, 0, &locking[1..], ty);
+            push(&mut tx.inputs[0].script_sig, &last);
+            push(&mut tx.inputs[0].script_sig, &first);
+            tx
+        };
+        assert!(verify(&unlock(&locking[second..]), 0, &locking));
+        // Each signature covers only the code after the last separator
+        assert!(!verify(&unlock(&locking), 0, &locking));
+    }
+
+    #[test]
+    fn test_checksig_requires_context() {
+        let mut stack = Stack::default();
+        let err = stack.execute(&[OP_1, OP_1, OP_CHECKSIG]).unwrap_err();
+        assert_eq!((err.kind, err.offset), (MissingContext, 2));
+    }
+
+    #[test]
+    fn test_input_index_out_of_range() {
+        let tx = spending_tx(1);
+        let locking = [OP_1, OP_1, OP_CHECKSIG];
+        let ctx = ExecutionContext {
+            tx: &tx,
+            input_index: 1,
+            amount: AMOUNT,
+            locking_script: &locking,
+            flags: ScriptFlags::default(),
+        };
+        assert_eq!(ctx.verify().unwrap_err().kind, InvalidInputIndex);
+        let mut stack = Stack::default();
+        let err = stack.execute_with(&locking, &ctx).unwrap_err();
+        assert_eq!((err.kind, err.offset), (InvalidInputIndex, 0));
+        assert!(stack.main.is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+//! Consensus conformance: runs the vectors transcribed from Bitcoin SV's
+//! `script_tests.json` (`data/script_tests.json`) and nPrint's own vectors in
+//! the same format (`data/nprint_script_tests.json`) through the interpreter.
+//! Each vector is `[scriptSig, scriptPubKey, flags, expected, comment?]`;
+//! single-element arrays are comments.
+//!
+//! Vectors whose flags the harness cannot model are skipped; every other
+//! vector must pass.
+
+#[cfg(test)]
+mod tests {
+    use nprint_core::ScriptErrorKind::{self, *};
+    use nprint_core::{
+        ExecutionContext, ScriptFlags, Transaction, TxInput, TxOutput, encode_num, opcode_from_name,
+    };
+    use serde_json::Value;
+
+    const VECTOR_FILES: &[(&str, &str)] = &[
+        ("script_tests.json", include_str!("data/script_tests.json")),
+        (
+            "nprint_script_tests.json",
+            include_str!("data/nprint_script_tests.json"),
+        ),
+    ];
+
+    /// Flags the interpreter applies unconditionally, so listing them in a
+    /// vector changes nothing. P2SH only matters for pay-to-script-hash
+    /// outputs, which `evaluate` refuses.
+    const ALWAYS_APPLIED: &[&str] = &[
+        "P2SH",
+        "STRICTENC",
+        "DERSIG",
+        "SIGHASH_FORKID",
+        "CHECKLOCKTIMEVERIFY",
+        "CHECKSEQUENCEVERIFY",
+    ];
+
+    /// Appends a minimal push of `data`.
+    fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+        match data.len() {
+            len @ 0..=0x4b => script.push(len as u8),
+            len @ 0x4c..=0xff => script.extend([0x4c, len as u8]),
+            len @ 0x100..=0xffff => {
+                script.push(0x4d);
+                script.extend((len as u16).to_le_bytes());
+            }
+            len => {
+                script.push(0x4e);
+                script.extend((len as u32).to_le_bytes());
+            }
+        }
+        script.extend_from_slice(data);
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        assert!(s.len().is_multiple_of(2), "Odd-length hex: {s}");
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("Invalid hex"))
+            .collect()
+    }
+
+    /// Assembles the test-vector script notation: decimal numbers, `0x` raw
+    /// bytes (inserted verbatim), `'quoted'` string pushes and opcode names
+    /// with or without the OP_ prefix.
+    fn assemble(asm: &str) -> Vec<u8> {
+        let mut script = Vec::new();
+        let mut rest = asm.trim_start();
+        while !rest.is_empty() {
+            let token;
+            if let Some(quoted) = rest.strip_prefix('\'') {
+                let end = quoted.find('\'').expect("Unterminated string");
+                push_data(&mut script, &quoted.as_bytes()[..end]);
+                token = &rest[..end + 2];
+            } else {
+                token = rest.split_whitespace().next().unwrap();
+                if let Some(bytes) = token.strip_prefix("0x") {
+                    script.extend(hex(bytes));
+                } else if let Some(op) = opcode_from_name(token) {
+                    script.push(op);
+                } else if let Ok(n) = token.parse::<i64>() {
+                    match n {
+                        -1 => script.push(0x4f),
+                        1..=16 => script.push(0x50 + n as u8),
+                        _ => push_data(&mut script, &encode_num(n)),
+                    }
+                } else {
+                    panic!("Unknown token in test vector: {token}");
+                }
+            }
+            rest = rest[token.len()..].trim_start();
+        }
+        script
+    }
+
+    /// Error kinds accepted for each expected result named in the vectors.
+    fn accepted_kinds(expected: &str) -> &'static [ScriptErrorKind] {
+        match expected {
+            "VERIFY"
+            | "EQUALVERIFY"
+            | "NUMEQUALVERIFY"
+            | "CHECKSIGVERIFY"
+            | "CHECKMULTISIGVERIFY" => &[VerifyFailed],
+            "INVALID_STACK_OPERATION" => &[StackUnderflow],
+            "INVALID_ALTSTACK_OPERATION" => &[AltStackUnderflow],
+            "UNBALANCED_CONDITIONAL" => &[UnbalancedConditional],
+            "BAD_OPCODE" => &[InvalidOpcode, TruncatedPush],
+            "DISABLED_OPCODE" => &[DisabledOpcode],
+            "DIV_BY_ZERO" | "MOD_BY_ZERO" => &[DivisionByZero],
+            "UNKNOWN_ERROR" => &[NumberOverflow, NonMinimalNumber],
+            "MINIMALDATA" => &[NonMinimalPush, NonMinimalNumber],
+            "SPLIT_RANGE" | "INVALID_NUMBER_RANGE" | "SIG_COUNT" | "PUBKEY_COUNT" => {
+                &[InvalidNumberRange]
+            }
+            "INVALID_OPERAND_SIZE" => &[OperandSizeMismatch],
+            "IMPOSSIBLE_ENCODING" => &[ImpossibleEncoding],
+            "PUSH_SIZE" => &[PushSizeExceeded],
+            "OP_RETURN" => &[EarlyReturn],
+            "SCRIPT_SIZE" => &[ScriptSizeExceeded],
+            "OP_COUNT" => &[OpCountExceeded],
+            "STACK_SIZE" => &[StackSizeExceeded],
+            "SIG_DER" | "SIG_HASHTYPE" => &[SignatureEncoding],
+            "MUST_USE_FORKID" => &[MissingForkId],
+            "PUBKEYTYPE" => &[PubKeyEncoding],
+            "NEGATIVE_LOCKTIME" => &[NegativeLocktime],
+            "UNSATISFIED_LOCKTIME" => &[UnsatisfiedLocktime],
+            _ => &[],
+        }
+    }
+
+    /// Maps a vector's flags column onto the interpreter's rules, or returns
+    /// `None` if it names a flag the interpreter does not model.
+    fn script_flags(flags: &str) -> Option<ScriptFlags> {
+        let mut rules = ScriptFlags::PRE_GENESIS;
+        let mut require_minimal = false;
+        for flag in flags.split(',').filter(|f| !f.is_empty()) {
+            match flag {
+                "UTXO_AFTER_GENESIS" => rules = ScriptFlags::GENESIS_CONSENSUS,
+                "MINIMALDATA" => require_minimal = true,
+                _ if ALWAYS_APPLIED.contains(&flag) => {}
+                _ => return None,
+            }
+        }
+        Some(ScriptFlags {
+            require_minimal,
+            ..rules
+        })
+    }
+
+    fn is_p2sh(script: &[u8]) -> bool {
+        matches!(script, [0xa9, 0x14, .., 0x87] if script.len() == 23)
+    }
+
+    /// Spends a zero-value output locked by `pubkey` from a one-input,
+    /// one-output transaction unlocked by `sig`.
+    fn evaluate(sig: &[u8], pubkey: &[u8], flags: ScriptFlags) -> Result<bool, ScriptErrorKind> {
+        let tx = Transaction {
+            version: 1,
+            inputs: vec![TxInput {
+                script_sig: sig.to_vec(),
+                sequence: 0xffff_ffff,
+                ..Default::default()
+            }],
+            outputs: vec![TxOutput::default()],
+            lock_time: 0,
+        };
+        let ctx = ExecutionContext {
+            tx: &tx,
+            input_index: 0,
+            amount: 0,
+            locking_script: pubkey,
+            flags,
+        };
+        ctx.verify().map_err(|e| e.kind)
+    }
+
+    fn matches(result: Result<bool, ScriptErrorKind>, expected: &str) -> bool {
+        match (result, expected) {
+            (Ok(success), "OK") => success,
+            (Ok(success), "EVAL_FALSE") => !success,
+            (Ok(_), _) => false,
+            (Err(kind), _) => accepted_kinds(expected).contains(&kind),
+        }
+    }
+
+    #[test]
+    fn test_script_vectors() {
+        let (mut total, mut skipped) = (0, 0);
+        let mut failures = Vec::new();
+        for (file, contents) in VECTOR_FILES {
+            let vectors: Vec<Value> =
+                serde_json::from_str(contents).expect("Malformed vector file");
+            for vector in &vectors {
+                let fields = vector.as_array().expect("Vector must be an array");
+                if fields.len() == 1 {
+                    continue;
+                }
+                let field = |i: usize| fields[i].as_str().expect("Vector fields must be strings");
+                let (sig, pubkey, flags, expected) = (field(0), field(1), field(2), field(3));
+                let (sig_bytes, pubkey_bytes) = (assemble(sig), assemble(pubkey));
+                total += 1;
+                let Some(rules) = script_flags(flags).filter(|_| !is_p2sh(&pubkey_bytes)) else {
+                    skipped += 1;
+                    continue;
+                };
+                let result = evaluate(&sig_bytes, &pubkey_bytes, rules);
+                if !matches(result, expected) {
+                    failures.push(format!(
+                        "{file}: [{} | {}] ({flags}) expected {expected}, got {result:?}",
+                        sig.trim(),
+                        pubkey.trim()
+                    ));
+                }
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "{} of {} script vectors failed ({skipped} skipped): {failures:#?}",
+            failures.len(),
+            total - skipped
+        );
+    }
+}
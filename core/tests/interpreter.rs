@@ -0,0 +1,478 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::ScriptErrorKind::*;
+    use nprint_core::{
+        ScriptError, ScriptErrorKind, ScriptFlags, Stack, decode_num, encode_num, hashcat,
+    };
+    use proptest::prelude::*;
+    use sha2::{Digest, Sha256};
+    use sv::script::op_codes::{
+        OP_0, OP_1, OP_1ADD, OP_1NEGATE, OP_2, OP_2DROP, OP_2DUP, OP_2MUL, OP_2OVER, OP_2ROT,
+        OP_2SWAP, OP_3, OP_3DUP, OP_4, OP_5, OP_9, OP_16, OP_ABS, OP_ADD, OP_AND, OP_BIN2NUM,
+        OP_CAT, OP_DEPTH, OP_DIV, OP_DROP, OP_DUP, OP_ELSE, OP_ENDIF, OP_EQUAL, OP_EQUALVERIFY,
+        OP_FALSE, OP_FROMALTSTACK, OP_HASH160, OP_HASH256, OP_IF, OP_IFDUP, OP_INVERT, OP_LESSTHAN,
+        OP_LSHIFT, OP_MOD, OP_MUL, OP_NIP, OP_NOP, OP_NOT, OP_NOTIF, OP_NUM2BIN, OP_NUMEQUALVERIFY,
+        OP_OR, OP_OVER, OP_PICK, OP_PUSHDATA1, OP_PUSHDATA2, OP_RETURN, OP_RIPEMD160, OP_ROLL,
+        OP_ROT, OP_RSHIFT, OP_SHA1, OP_SHA256, OP_SIZE, OP_SPLIT, OP_SUB, OP_SWAP, OP_TOALTSTACK,
+        OP_TUCK, OP_VERIFY, OP_WITHIN, OP_XOR,
+    };
+
+    fn run(script: &[u8]) -> Result<Vec<Vec<u8>>, ScriptError> {
+        let mut stack = Stack::default();
+        stack.execute(script)?;
+        Ok(stack.main)
+    }
+
+    fn kind(script: &[u8]) -> ScriptErrorKind {
+        run(script).unwrap_err().kind
+    }
+
+    fn run_with(flags: ScriptFlags, script: &[u8]) -> Result<Vec<Vec<u8>>, ScriptErrorKind> {
+        let mut stack = Stack {
+            flags,
+            ..Default::default()
+        };
+        stack.execute(script).map_err(|e| e.kind)?;
+        Ok(stack.main)
+    }
+
+    proptest! {
+        #[test]
+        fn test_no_panic_on_arbitrary_script(script in proptest::collection::vec(any::<u8>(), 0..64)) {
+            let mut stack = Stack::default();
+            let _ = stack.execute(&script);
+            // Memory is tracked incrementally, even up to a failure
+            let memory: usize = stack.main.iter().chain(&stack.alt).map(|i| i.len() + 32).sum();
+            prop_assert_eq!(stack.memory, memory);
+        }
+
+        #[test]
+        fn test_stack_memory(script in proptest::collection::vec(prop::sample::select(vec![
+            OP_1, OP_2, OP_16, OP_1NEGATE, OP_DUP, OP_2DUP, OP_3DUP, OP_2OVER, OP_2DROP, OP_2ROT,
+            OP_2SWAP, OP_NIP, OP_OVER, OP_PICK, OP_ROLL, OP_ROT, OP_SWAP, OP_TUCK, OP_DROP,
+            OP_TOALTSTACK, OP_FROMALTSTACK, OP_CAT, OP_SPLIT, OP_ADD,
+        ]), 0..48)) {
+            let mut stack = Stack::default();
+            let _ = stack.execute(&script);
+            let memory: usize = stack.main.iter().chain(&stack.alt).map(|i| i.len() + 32).sum();
+            prop_assert_eq!(stack.memory, memory);
+        }
+
+        #[test]
+        fn test_num_roundtrip(n in -(1i64 << 39)..(1i64 << 39)) {
+            prop_assert_eq!(decode_num(&encode_num(n), 8, true), Ok(n));
+        }
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut stack = Stack::default();
+        stack.push(vec![7]);
+        assert_eq!(stack.memory, 33);
+        assert_eq!(stack.pop(), Ok(vec![7]));
+        assert_eq!(stack.memory, 0);
+        assert_eq!(stack.pop(), Err(StackUnderflow));
+    }
+
+    #[test]
+    fn test_composite_add() {
+        let script = vec![OP_2, OP_3, OP_ADD, OP_5, OP_EQUAL];
+        assert_eq!(run(&script).unwrap(), vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_arithmetic_results() {
+        assert_eq!(run(&[OP_2, OP_5, OP_SUB]).unwrap(), vec![vec![0x83]]);
+        assert_eq!(run(&[OP_1NEGATE, OP_ABS]).unwrap(), vec![vec![1]]);
+        assert_eq!(run(&[OP_FALSE, OP_NOT]).unwrap(), vec![vec![1]]);
+        // Truncating division and dividend-signed remainder, as in consensus
+        assert_eq!(run(&[1, 0x87, OP_2, OP_DIV]).unwrap(), vec![vec![0x83]]);
+        assert_eq!(run(&[1, 0x87, OP_2, OP_MOD]).unwrap(), vec![vec![0x81]]);
+        assert_eq!(
+            run(&[1, 0x7f, OP_2, OP_MUL]).unwrap(),
+            vec![vec![0xfe, 0x00]]
+        );
+        assert_eq!(run(&[OP_3, OP_2, OP_5, OP_WITHIN]).unwrap(), vec![vec![1]]);
+        assert_eq!(
+            run(&[OP_5, OP_3, OP_LESSTHAN]).unwrap(),
+            vec![Vec::<u8>::new()]
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_errors() {
+        assert_eq!(kind(&[OP_1, OP_FALSE, OP_DIV]), DivisionByZero);
+        assert_eq!(kind(&[OP_1, OP_2, OP_NUMEQUALVERIFY]), VerifyFailed);
+        // Non-minimal encoding of 1
+        assert_eq!(kind(&[2, 0x01, 0x00, OP_1, OP_ADD]), NonMinimalNumber);
+        // Operands longer than four bytes are only allowed after Genesis
+        let script = [5, 1, 0, 0, 0, 1, OP_1, OP_ADD];
+        assert_eq!(
+            run_with(ScriptFlags::PRE_GENESIS, &script),
+            Err(NumberOverflow)
+        );
+        assert_eq!(run(&script).unwrap(), vec![vec![2, 0, 0, 0, 1]]);
+    }
+
+    #[test]
+    fn test_if_else_branches() {
+        let script = [OP_IF, OP_2, OP_ELSE, OP_3, OP_ENDIF];
+        assert_eq!(
+            run(&[&[OP_1][..], &script].concat()).unwrap(),
+            vec![vec![2]]
+        );
+        assert_eq!(
+            run(&[&[OP_FALSE][..], &script].concat()).unwrap(),
+            vec![vec![3]]
+        );
+        let script = [OP_1, OP_NOTIF, OP_2, OP_ENDIF];
+        assert_eq!(run(&script).unwrap(), Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    fn test_skipped_branch_push_data() {
+        // The skipped PUSHDATA1 payload contains bytes that look like OP_ENDIF/OP_RETURN
+        let script = [
+            OP_FALSE,
+            OP_IF,
+            OP_PUSHDATA1,
+            2,
+            OP_ENDIF,
+            OP_RETURN,
+            OP_ELSE,
+            OP_5,
+            OP_ENDIF,
+        ];
+        assert_eq!(run(&script).unwrap(), vec![vec![5]]);
+    }
+
+    #[test]
+    fn test_unbalanced_conditionals() {
+        assert_eq!(kind(&[OP_1, OP_IF]), UnbalancedConditional);
+        assert_eq!(kind(&[OP_ENDIF]), UnbalancedConditional);
+        assert_eq!(kind(&[OP_ELSE]), UnbalancedConditional);
+        assert_eq!(kind(&[OP_FALSE, OP_IF, OP_RETURN]), UnbalancedConditional);
+    }
+
+    #[test]
+    fn test_verify_and_return() {
+        assert_eq!(kind(&[OP_FALSE, OP_VERIFY]), VerifyFailed);
+        assert_eq!(kind(&[OP_1, OP_2, OP_EQUALVERIFY]), VerifyFailed);
+        assert!(run(&[OP_2, OP_2, OP_EQUALVERIFY]).unwrap().is_empty());
+        // OP_RETURN ends execution; the trailing bytes are never evaluated
+        assert_eq!(run(&[OP_1, OP_RETURN, 0xff]).unwrap(), vec![vec![1]]);
+        assert_eq!(
+            run(&[OP_1, OP_IF, OP_RETURN, OP_2, OP_ENDIF]).unwrap(),
+            Vec::<Vec<u8>>::new()
+        );
+    }
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_hash_opcodes_empty_input() {
+        let vectors = [
+            (OP_RIPEMD160, "9c1185a5c5e9fc54612808977ee8f548b2258d31"),
+            (OP_SHA1, "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            (
+                OP_SHA256,
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (OP_HASH160, "b472a266d0bd89c13706a4132ccfb16f7c3b9fcb"),
+            (
+                OP_HASH256,
+                "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456",
+            ),
+        ];
+        for (op, digest) in vectors {
+            assert_eq!(run(&[OP_FALSE, op]).unwrap(), vec![hex(digest)], "op {op}");
+        }
+    }
+
+    #[test]
+    fn test_hash_underflow() {
+        assert_eq!(kind(&[OP_SHA256]), StackUnderflow);
+    }
+
+    #[test]
+    fn test_cat_and_split() {
+        let script = [2, 0xaa, 0xbb, 1, 0xcc, OP_CAT, OP_SIZE];
+        assert_eq!(run(&script).unwrap(), vec![vec![0xaa, 0xbb, 0xcc], vec![3]]);
+        let script = [3, 0xaa, 0xbb, 0xcc, OP_1, OP_SPLIT];
+        assert_eq!(run(&script).unwrap(), vec![vec![0xaa], vec![0xbb, 0xcc]]);
+        assert_eq!(
+            kind(&[3, 0xaa, 0xbb, 0xcc, OP_4, OP_SPLIT]),
+            InvalidNumberRange
+        );
+        assert!(run(&[OP_1, OP_1NEGATE, OP_SPLIT]).is_err());
+    }
+
+    #[test]
+    fn test_cat_element_size_limit() {
+        let mut stack = Stack {
+            flags: ScriptFlags::PRE_GENESIS,
+            ..Default::default()
+        };
+        stack.push(vec![0; 500]);
+        stack.push(vec![0; 21]);
+        assert_eq!(stack.execute(&[OP_CAT]).unwrap_err().kind, PushSizeExceeded);
+        let mut stack = Stack::default();
+        stack.push(vec![0; 500]);
+        stack.push(vec![0; 21]);
+        stack.execute(&[OP_CAT]).unwrap();
+        assert_eq!(stack.main, vec![vec![0; 521]]);
+    }
+
+    #[test]
+    fn test_num2bin_and_bin2num() {
+        assert_eq!(
+            run(&[OP_1NEGATE, OP_4, OP_NUM2BIN]).unwrap(),
+            vec![vec![0x01, 0x00, 0x00, 0x80]]
+        );
+        assert_eq!(
+            run(&[OP_2, OP_3, OP_NUM2BIN]).unwrap(),
+            vec![vec![0x02, 0x00, 0x00]]
+        );
+        assert_eq!(kind(&[2, 0xff, 0x00, OP_1, OP_NUM2BIN]), ImpossibleEncoding);
+        assert_eq!(
+            run(&[4, 0x01, 0x00, 0x00, 0x80, OP_BIN2NUM]).unwrap(),
+            vec![vec![0x81]]
+        );
+        assert_eq!(
+            run(&[2, 0x00, 0x80, OP_BIN2NUM]).unwrap(),
+            vec![Vec::<u8>::new()]
+        );
+        let script = [5, 0x01, 0x00, 0x00, 0x00, 0x01, OP_BIN2NUM];
+        assert_eq!(
+            run_with(ScriptFlags::PRE_GENESIS, &script),
+            Err(NumberOverflow)
+        );
+        assert!(run(&script).is_ok());
+    }
+
+    #[test]
+    fn test_hashcat() {
+        let data = b"nprint".to_vec();
+        let mut stack = Stack::default();
+        stack.push(data.clone());
+        stack.execute(&hashcat!()).unwrap();
+        let hash160 = ripemd::Ripemd160::digest(Sha256::digest(&data));
+        assert_eq!(stack.main, vec![[&data[..], &hash160[..]].concat()]);
+    }
+
+    fn run_on(inputs: &[&[u8]], script: &[u8]) -> Vec<Vec<u8>> {
+        let mut stack = Stack::default();
+        inputs.iter().for_each(|item| stack.push(item.to_vec()));
+        stack.execute(script).unwrap();
+        stack.main
+    }
+
+    #[test]
+    fn test_bitwise_vectors() {
+        let (a, b): (&[u8], &[u8]) = (&[0x0f, 0x0f], &[0xf0, 0xff]);
+        assert_eq!(run_on(&[a, b], &[OP_AND]), vec![vec![0x00, 0x0f]]);
+        assert_eq!(run_on(&[a, b], &[OP_OR]), vec![vec![0xff, 0xff]]);
+        assert_eq!(run_on(&[a, b], &[OP_XOR]), vec![vec![0xff, 0xf0]]);
+        assert_eq!(run_on(&[a, a], &[OP_XOR]), vec![vec![0x00, 0x00]]);
+        assert_eq!(
+            run_on(&[&[0x0f, 0x80]], &[OP_INVERT]),
+            vec![vec![0xf0, 0x7f]]
+        );
+        assert_eq!(kind(&[1, 0x11, 2, 0x11, 0x11, OP_AND]), OperandSizeMismatch);
+    }
+
+    #[test]
+    fn test_shift_vectors() {
+        let vectors: [(&[u8], u8, u8, &[u8]); 7] = [
+            (&[0x9f, 0x11], OP_1, OP_LSHIFT, &[0x3e, 0x22]),
+            (&[0x9f, 0x11], OP_1, OP_RSHIFT, &[0x4f, 0x88]),
+            (&[0x01, 0x80], OP_1, OP_LSHIFT, &[0x03, 0x00]),
+            (&[0x01, 0x80], OP_9, OP_RSHIFT, &[0x00, 0x00]),
+            (&[0x80, 0x01], OP_9, OP_LSHIFT, &[0x02, 0x00]),
+            // The sign bit shifts like any other bit
+            (&[0x80, 0x00], OP_4, OP_RSHIFT, &[0x08, 0x00]),
+            (&[0xff], OP_16, OP_LSHIFT, &[0x00]),
+        ];
+        for (data, n, op, expected) in vectors {
+            assert_eq!(run_on(&[data], &[n, op]), vec![expected.to_vec()]);
+        }
+        assert!(run(&[1, 0xff, OP_1NEGATE, OP_LSHIFT]).is_err());
+    }
+
+    #[test]
+    fn test_stack_manipulation() {
+        let items: [&[u8]; 6] = [&[1], &[2], &[3], &[4], &[5], &[6]];
+        let vectors: [(u8, Vec<u8>); 12] = [
+            (OP_2DROP, vec![1, 2, 3, 4]),
+            (OP_2DUP, vec![1, 2, 3, 4, 5, 6, 5, 6]),
+            (OP_3DUP, vec![1, 2, 3, 4, 5, 6, 4, 5, 6]),
+            (OP_2OVER, vec![1, 2, 3, 4, 5, 6, 3, 4]),
+            (OP_2ROT, vec![3, 4, 5, 6, 1, 2]),
+            (OP_2SWAP, vec![1, 2, 5, 6, 3, 4]),
+            (OP_IFDUP, vec![1, 2, 3, 4, 5, 6, 6]),
+            (OP_DEPTH, vec![1, 2, 3, 4, 5, 6, 6]),
+            (OP_NIP, vec![1, 2, 3, 4, 6]),
+            (OP_OVER, vec![1, 2, 3, 4, 5, 6, 5]),
+            (OP_ROT, vec![1, 2, 3, 5, 6, 4]),
+            (OP_TUCK, vec![1, 2, 3, 4, 6, 5, 6]),
+        ];
+        for (op, expected) in vectors {
+            let expected: Vec<Vec<u8>> = expected.into_iter().map(|b| vec![b]).collect();
+            assert_eq!(run_on(&items, &[op]), expected, "op {op}");
+        }
+        assert_eq!(run_on(&[&[]], &[OP_IFDUP]), vec![Vec::<u8>::new()]);
+    }
+
+    #[test]
+    fn test_stack_manipulation_underflow() {
+        for op in [OP_2DROP, OP_2DUP, OP_NIP, OP_OVER, OP_TUCK] {
+            assert!(run(&[OP_1, op]).is_err(), "op {op}");
+        }
+        for op in [OP_3DUP, OP_2OVER, OP_2ROT, OP_2SWAP, OP_ROT] {
+            assert!(run(&[OP_1, OP_1, op]).is_err(), "op {op}");
+        }
+        assert!(run(&[OP_IFDUP]).is_err());
+        assert_eq!(kind(&[OP_FROMALTSTACK]), AltStackUnderflow);
+    }
+
+    #[test]
+    fn test_alt_stack() {
+        let mut stack = Stack::default();
+        stack.execute(&[OP_1, OP_2, OP_TOALTSTACK, OP_3]).unwrap();
+        assert_eq!(stack.main, vec![vec![1], vec![3]]);
+        assert_eq!(stack.alt, vec![vec![2]]);
+        stack.execute(&[OP_FROMALTSTACK, OP_ADD]).unwrap();
+        assert_eq!(stack.main, vec![vec![1], vec![5]]);
+        assert!(stack.alt.is_empty());
+    }
+
+    #[test]
+    fn test_error_reports_opcode_and_offset() {
+        let err = run(&[OP_1, 2, 0xaa, 0xbb, OP_2DROP, OP_SWAP]).unwrap_err();
+        assert_eq!(
+            err,
+            ScriptError {
+                kind: StackUnderflow,
+                opcode: OP_SWAP,
+                offset: 5,
+            }
+        );
+        let err = run(&[OP_1, OP_IF, OP_1]).unwrap_err();
+        assert_eq!((err.kind, err.offset), (UnbalancedConditional, 3));
+        assert_eq!(kind(&[OP_PUSHDATA1, 5, 0xaa]), TruncatedPush);
+        assert_eq!(kind(&[OP_FALSE, OP_PICK]), StackUnderflow);
+        assert_eq!(kind(&[OP_1, OP_1NEGATE, OP_ROLL]), StackUnderflow);
+        assert_eq!(kind(&[0xba]), InvalidOpcode);
+        // OP_VERIF and OP_VERNOTIF fail even where they are not executed
+        assert!(run(&[OP_FALSE, OP_IF, 0xba, OP_ENDIF]).is_ok());
+        for op in [0x65, 0x66] {
+            let err = run(&[OP_FALSE, OP_IF, op, OP_ENDIF]).unwrap_err();
+            assert_eq!((err.kind, err.opcode, err.offset), (InvalidOpcode, op, 2));
+        }
+    }
+
+    #[test]
+    fn test_script_flag_limits() {
+        let flags = ScriptFlags::GENESIS_POLICY;
+        let limited = |f: fn(&mut ScriptFlags)| {
+            let mut flags = flags;
+            f(&mut flags);
+            flags
+        };
+        let nops = [OP_NOP; 4];
+        let err = |flags, script: &[u8]| run_with(flags, script).unwrap_err();
+        assert_eq!(
+            err(limited(|f| f.max_script_size = 3), &nops),
+            ScriptSizeExceeded
+        );
+        assert_eq!(err(limited(|f| f.max_ops = 3), &nops), OpCountExceeded);
+        // Pushes do not count as opcodes
+        assert!(run_with(limited(|f| f.max_ops = 0), &[OP_1, OP_2]).is_ok());
+        assert_eq!(
+            err(limited(|f| f.max_stack_items = 2), &[OP_1, OP_2, OP_3]),
+            StackSizeExceeded
+        );
+        assert_eq!(
+            err(
+                limited(|f| f.max_stack_items = 1),
+                &[OP_1, OP_DUP, OP_TOALTSTACK]
+            ),
+            StackSizeExceeded
+        );
+        // Two one-byte elements use 2 * (1 + 32) bytes
+        assert!(run_with(limited(|f| f.max_stack_memory = 66), &[OP_1, OP_2]).is_ok());
+        assert_eq!(
+            err(limited(|f| f.max_stack_memory = 65), &[OP_1, OP_2]),
+            StackSizeExceeded
+        );
+        // Popped and dropped items stop counting, moved ones keep counting
+        let script = [
+            OP_1,
+            OP_2,
+            OP_ADD,
+            OP_DUP,
+            OP_2DROP,
+            OP_1,
+            OP_TOALTSTACK,
+            OP_2,
+        ];
+        assert!(run_with(limited(|f| f.max_stack_memory = 66), &script).is_ok());
+        assert_eq!(
+            err(limited(|f| f.max_stack_memory = 65), &script),
+            StackSizeExceeded
+        );
+        assert_eq!(
+            err(limited(|f| f.max_element_size = 1), &[2, 0xaa, 0xbb]),
+            PushSizeExceeded
+        );
+    }
+
+    #[test]
+    fn test_require_minimal() {
+        let policy = ScriptFlags::GENESIS_POLICY;
+        let consensus = ScriptFlags::GENESIS_CONSENSUS;
+        assert!(policy.require_minimal);
+        assert!(!consensus.require_minimal && !ScriptFlags::PRE_GENESIS.require_minimal);
+
+        // Pushes must use the shortest encoding, OP_1 to OP_16 included
+        for script in [
+            &[0x01, 0x05][..],
+            &[0x01, 0x81],
+            &[OP_PUSHDATA1, 0x01, 0x20],
+            &[OP_PUSHDATA2, 0x01, 0x00, 0x20],
+        ] {
+            assert_eq!(run_with(policy, script), Err(NonMinimalPush), "{script:?}");
+            assert!(run_with(consensus, script).is_ok(), "{script:?}");
+        }
+        assert!(run_with(policy, &[0x01, 0x20, OP_0, OP_1NEGATE]).is_ok());
+        // ...when executed
+        let script = [OP_0, OP_IF, 0x01, 0x05, OP_ENDIF];
+        assert!(run_with(policy, &script).is_ok());
+
+        // Numeric operands must carry no padding
+        let script = [0x02, 0x01, 0x00, OP_1ADD];
+        assert_eq!(run_with(policy, &script), Err(NonMinimalNumber));
+        assert_eq!(run_with(consensus, &script), Ok(vec![vec![2]]));
+    }
+
+    #[test]
+    fn test_pre_genesis_rules() {
+        let pre = ScriptFlags::PRE_GENESIS;
+        assert_eq!(run_with(pre, &[OP_1, OP_RETURN]), Err(EarlyReturn));
+        assert!(run_with(pre, &[OP_FALSE, OP_IF, OP_RETURN, OP_ENDIF]).is_ok());
+        assert_eq!(run(&[OP_1, OP_RETURN]).unwrap(), vec![vec![1]]);
+        // Disabled opcodes fail even when not executed, under every rule set
+        let script = [OP_FALSE, OP_IF, OP_2MUL, OP_ENDIF];
+        assert_eq!(run_with(pre, &script), Err(DisabledOpcode));
+        assert_eq!(kind(&script), DisabledOpcode);
+        let push = [&[OP_PUSHDATA2, 0x09, 0x02][..], &[0; 521]].concat();
+        assert_eq!(run_with(pre, &push), Err(PushSizeExceeded));
+        assert!(run(&push).is_ok());
+        assert_eq!(run_with(pre, &[OP_NOP; 501]), Err(OpCountExceeded));
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::ScriptErrorKind::{self, *};
+    use nprint_core::{ExecutionContext, ScriptFlags, Stack, Transaction, TxInput, encode_num};
+    use sv::script::op_codes::{OP_1, OP_CHECKLOCKTIMEVERIFY, OP_CHECKSEQUENCEVERIFY};
+
+    const TIME: i64 = 1_700_000_000;
+    const TYPE_FLAG: u32 = 1 << 22;
+    const DISABLE_FLAG: u32 = 1 << 31;
+
+    fn tx(version: u32, lock_time: u32, sequence: u32) -> Transaction {
+        Transaction {
+            version,
+            inputs: vec![TxInput {
+                sequence,
+                ..Default::default()
+            }],
+            outputs: vec![],
+            lock_time,
+        }
+    }
+
+    fn run(tx: &Transaction, op: u8, n: i64, flags: ScriptFlags) -> Result<(), ScriptErrorKind> {
+        let data = encode_num(n);
+        let mut script = vec![data.len() as u8];
+        script.extend(data);
+        script.push(op);
+        let ctx = ExecutionContext {
+            tx,
+            input_index: 0,
+            amount: 0,
+            locking_script: &[],
+            flags,
+        };
+        Stack {
+            flags,
+            ..Default::default()
+        }
+        .execute_with(&script, &ctx)
+        .map_err(|e| e.kind)
+    }
+
+    fn cltv(tx: &Transaction, n: i64) -> Result<(), ScriptErrorKind> {
+        run(tx, OP_CHECKLOCKTIMEVERIFY, n, ScriptFlags::PRE_GENESIS)
+    }
+
+    fn csv(tx: &Transaction, n: i64) -> Result<(), ScriptErrorKind> {
+        run(tx, OP_CHECKSEQUENCEVERIFY, n, ScriptFlags::PRE_GENESIS)
+    }
+
+    #[test]
+    fn test_checklocktimeverify() {
+        let height = tx(1, 800_000, 0);
+        assert_eq!(cltv(&height, 799_999), Ok(()));
+        assert_eq!(cltv(&height, 800_000), Ok(()));
+        assert_eq!(cltv(&height, 800_001), Err(UnsatisfiedLocktime));
+        // Heights and timestamps do not compare
+        assert_eq!(cltv(&height, TIME), Err(UnsatisfiedLocktime));
+
+        let time = tx(1, TIME as u32, 0);
+        assert_eq!(cltv(&time, TIME - 1), Ok(()));
+        assert_eq!(cltv(&time, TIME + 1), Err(UnsatisfiedLocktime));
+        assert_eq!(cltv(&time, 100), Err(UnsatisfiedLocktime));
+
+        // A final sequence disables the transaction's lock time
+        assert_eq!(
+            cltv(&tx(1, 800_000, u32::MAX), 100),
+            Err(UnsatisfiedLocktime)
+        );
+        assert_eq!(cltv(&height, -1), Err(NegativeLocktime));
+        assert_eq!(cltv(&tx(1, u32::MAX, 0), u32::MAX as i64), Ok(()));
+    }
+
+    #[test]
+    fn test_checksequenceverify() {
+        let blocks = tx(2, 0, 10);
+        assert_eq!(csv(&blocks, 10), Ok(()));
+        assert_eq!(csv(&blocks, 11), Err(UnsatisfiedLocktime));
+        // Only the type flag and low 16 bits are compared
+        assert_eq!(csv(&blocks, (1 << 16) | 10), Ok(()));
+        assert_eq!(csv(&blocks, TYPE_FLAG as i64 | 1), Err(UnsatisfiedLocktime));
+
+        let seconds = tx(2, 0, TYPE_FLAG | 10);
+        assert_eq!(csv(&seconds, TYPE_FLAG as i64 | 10), Ok(()));
+        assert_eq!(csv(&seconds, 1), Err(UnsatisfiedLocktime));
+
+        // Version 1 transactions and inputs with the disable flag cannot satisfy CSV
+        assert_eq!(csv(&tx(1, 0, 10), 10), Err(UnsatisfiedLocktime));
+        assert_eq!(
+            csv(&tx(2, 0, DISABLE_FLAG | 10), 10),
+            Err(UnsatisfiedLocktime)
+        );
+        // ...unless the script's lock has the disable flag
+        assert_eq!(csv(&tx(1, 0, 0), DISABLE_FLAG as i64), Ok(()));
+        assert_eq!(csv(&blocks, -1), Err(NegativeLocktime));
+        // Versions are signed, so 0x80000000 and up are below 2
+        assert_eq!(csv(&tx(0x8000_0000, 0, 10), 10), Err(UnsatisfiedLocktime));
+        assert_eq!(csv(&tx(u32::MAX, 0, 10), 10), Err(UnsatisfiedLocktime));
+        assert_eq!(csv(&tx(0x7fff_ffff, 0, 10), 10), Ok(()));
+
+        // A context naming a missing input is an error, not a panic
+        let mut missing = tx(2, 0, 10);
+        missing.inputs.clear();
+        assert_eq!(csv(&missing, 10), Err(InvalidInputIndex));
+        assert_eq!(cltv(&missing, 10), Err(InvalidInputIndex));
+    }
+
+    #[test]
+    fn test_locktime_nop_after_genesis() {
+        let tx = tx(1, 0, u32::MAX);
+        assert_eq!(cltv(&tx, 800_000), Err(UnsatisfiedLocktime));
+        assert_eq!(
+            run(
+                &tx,
+                OP_CHECKLOCKTIMEVERIFY,
+                800_000,
+                ScriptFlags::GENESIS_CONSENSUS
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            run(
+                &tx,
+                OP_CHECKSEQUENCEVERIFY,
+                -1,
+                ScriptFlags::GENESIS_CONSENSUS
+            ),
+            Ok(())
+        );
+        assert!(
+            Stack::default()
+                .execute(&[OP_1, OP_CHECKSEQUENCEVERIFY])
+                .is_ok()
+        );
+        let err = Stack {
+            flags: ScriptFlags::PRE_GENESIS,
+            ..Default::default()
+        }
+        .execute(&[OP_1, OP_CHECKSEQUENCEVERIFY])
+        .unwrap_err();
+        assert_eq!(err.kind, MissingContext);
+    }
+}
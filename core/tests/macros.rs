@@ -1,8 +1,77 @@
 #[cfg(test)]
 mod tests {
-    use nprint_core::{Stack, bsv_script, loop_unroll, xswap};
+    use nprint_core::MacroErrorKind::*;
+    use nprint_core::{
+        MacroArg, MacroDef, MacroElem, MacroError, MacroRegistry, ScriptErrorKind, ScriptNum,
+        Stack, alt_push_n, assert_eq_verify, bsv_script, drop_n, expand_macro, for_each_byte,
+        hashcat, loop_unroll, num2bin_le, opcode_name, reverse_bytes, std_macros, to_asm, xdrop,
+        xdup, xpick, xrot, xswap,
+    };
     use proptest::prelude::*;
-    use sv::script::op_codes::{OP_DUP, OP_ROLL, OP_SWAP};
+    use sha2::{Digest, Sha256};
+    use sv::script::op_codes::{
+        OP_0, OP_1, OP_1NEGATE, OP_2, OP_16, OP_ADD, OP_CAT, OP_DROP, OP_DUP, OP_PICK,
+        OP_PUSHDATA1, OP_PUSHDATA2, OP_ROLL, OP_SWAP,
+    };
+
+    /// Executes `script` on `items`, bottom first.
+    fn run(items: &[&[u8]], script: &[u8]) -> Stack {
+        let mut stack = Stack::default();
+        for item in items {
+            stack.push(item.to_vec());
+        }
+        stack.execute(script).unwrap();
+        stack
+    }
+
+    fn def(name: &str, param_count: usize, template: Vec<MacroElem>) -> MacroDef {
+        MacroDef {
+            name: name.to_string(),
+            param_count,
+            template,
+            effect: None,
+        }
+    }
+
+    fn call(name: &str, args: Vec<MacroArg>) -> MacroElem {
+        MacroElem::Macro(name.to_string(), args)
+    }
+
+    fn err(kind: nprint_core::MacroErrorKind, name: &str) -> MacroError {
+        MacroError {
+            kind,
+            name: name.to_string(),
+        }
+    }
+
+    /// xroll(n) = n OP_ROLL, with xrot, xswap and xdrop built on it.
+    fn primitives() -> MacroRegistry {
+        let mut registry = MacroRegistry::new();
+        registry.insert(def(
+            "xroll",
+            1,
+            vec![MacroElem::Param(0), MacroElem::Op(OP_ROLL)],
+        ));
+        registry.insert(def(
+            "xrot",
+            1,
+            vec![call("xroll", vec![MacroArg::Param(0)])],
+        ));
+        registry.insert(def(
+            "xswap",
+            1,
+            vec![call("xroll", vec![MacroArg::Param(1)])],
+        ));
+        registry.insert(def(
+            "xdrop",
+            1,
+            vec![
+                call("xswap", vec![MacroArg::Param(0)]),
+                MacroElem::Op(OP_DROP),
+            ],
+        ));
+        registry
+    }
 
     proptest! {
         #[test]
@@ -38,4 +107,367 @@ mod tests {
         assert_eq!(stack.main.len(), 3);
         assert_eq!(stack.main, vec![vec![0], vec![2], vec![1]]);
     }
+
+    #[test]
+    fn test_loop_unroll_index() {
+        const N: usize = 4;
+        // Each hash lands on top, so original item i sits at depth 2 * i
+        let script = loop_unroll!(i in 0..N, { (2 * i), OP_PICK, OP_SHA256 });
+        let items: [&[u8]; N] = [b"a", b"b", b"c", b"d"];
+        let stack = run(&items, &script);
+        let hashes = items.iter().rev().map(|item| Sha256::digest(item).to_vec());
+        assert_eq!(stack.main[N..], hashes.collect::<Vec<_>>());
+
+        // Large and negative indices push minimally
+        assert_eq!(loop_unroll!(i in (-1)..=1, { i }), [OP_1NEGATE, OP_0, OP_1]);
+        assert_eq!(loop_unroll!(i in 16..18, { i }), [OP_16, 1, 17]);
+
+        // Nested loops see both indices, and expressions use them
+        let script = loop_unroll!(i in 0..3, {
+            loop_unroll!(j in 0..2, { (i * 10 + j) })
+        });
+        let expected: Vec<u8> = [0u8, 1, 10, 11, 20, 21]
+            .iter()
+            .flat_map(|&n| bsv_script! { n })
+            .collect();
+        assert_eq!(script, expected);
+
+        // Other macros splice into the body
+        let script = loop_unroll!(i in 1..=2, { xpick!(i), OP_DROP });
+        assert_eq!(script, [OP_1, OP_PICK, OP_DROP, OP_2, OP_PICK, OP_DROP]);
+    }
+
+    #[test]
+    fn test_bsv_script_opcodes() {
+        let script = bsv_script! {
+            OP_0, OP_FALSE, OP_1NEGATE, OP_TRUE, OP_16, OP_NOP, OP_VER, OP_IF, OP_NOTIF,
+            OP_VERIF, OP_VERNOTIF, OP_ELSE, OP_ENDIF, OP_VERIFY, OP_RETURN, OP_TOALTSTACK,
+            OP_FROMALTSTACK, OP_2DROP, OP_2DUP, OP_3DUP, OP_2OVER, OP_2ROT, OP_2SWAP, OP_IFDUP,
+            OP_DEPTH, OP_DROP, OP_DUP, OP_NIP, OP_OVER, OP_PICK, OP_ROLL, OP_ROT, OP_SWAP,
+            OP_TUCK, OP_CAT, OP_SPLIT, OP_NUM2BIN, OP_BIN2NUM, OP_SIZE, OP_INVERT, OP_AND,
+            OP_OR, OP_XOR, OP_EQUAL, OP_EQUALVERIFY, OP_RESERVED1, OP_RESERVED2, OP_1ADD,
+            OP_1SUB, OP_2MUL, OP_2DIV, OP_NEGATE, OP_ABS, OP_NOT, OP_0NOTEQUAL, OP_ADD, OP_SUB,
+            OP_MUL, OP_DIV, OP_MOD, OP_LSHIFT, OP_RSHIFT, OP_BOOLAND, OP_BOOLOR, OP_NUMEQUAL,
+            OP_NUMEQUALVERIFY, OP_NUMNOTEQUAL, OP_LESSTHAN, OP_GREATERTHAN, OP_LESSTHANOREQUAL,
+            OP_GREATERTHANOREQUAL, OP_MIN, OP_MAX, OP_WITHIN, OP_RIPEMD160, OP_SHA1, OP_SHA256,
+            OP_HASH160, OP_HASH256, OP_CODESEPARATOR, OP_CHECKSIG, OP_CHECKSIGVERIFY,
+            OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY, OP_NOP1, OP_NOP2, OP_CHECKSEQUENCEVERIFY,
+            OP_NOP4, OP_NOP5, OP_NOP6, OP_NOP7, OP_NOP8, OP_NOP9, OP_NOP10,
+        };
+        let mut expected = vec![0x00, 0x00, 0x4f, 0x51, 0x60];
+        expected.extend(0x61..=0xb9);
+        assert_eq!(script, expected);
+        // Names agree with the disassembler's table
+        for op in 0x61..=0xb9u8 {
+            assert!(opcode_name(op).is_some());
+        }
+        assert_eq!(bsv_script! { OP_1, OP_2, OP_ADD }, vec![0x51, 0x52, OP_ADD]);
+        assert_eq!(bsv_script! {}, Vec::<u8>::new());
+        assert_eq!(bsv_script! { OP_DUP, }, vec![OP_DUP]);
+    }
+
+    #[test]
+    fn test_bsv_script_long() {
+        // Items expand side by side, so length is not limited by macro recursion
+        let script = bsv_script! {
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP,
+            OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1, OP_DROP, OP_1,
+            OP_DROP
+        };
+        assert_eq!(script, [0x51, OP_DROP].repeat(105));
+    }
+
+    #[test]
+    fn test_bsv_script_pushes() {
+        assert_eq!(
+            bsv_script! { hex!("00ffAB"), b"hi", b"" },
+            vec![3, 0x00, 0xff, 0xab, 2, b'h', b'i', 0]
+        );
+        // Single bytes with a number opcode are pushed minimally
+        assert_eq!(
+            bsv_script! { hex!("01"), hex!("10"), hex!("81") },
+            vec![0x51, 0x60, 0x4f]
+        );
+        assert_eq!(
+            bsv_script! { hex!("00"), hex!("11"), &[0x81u8, 0] },
+            vec![1, 0, 1, 0x11, 2, 0x81, 0]
+        );
+        assert_eq!(bsv_script! { 1 }, vec![0x51]);
+
+        let script = bsv_script! { b"0123456789012345678901234567890123456789012345678901234567890123456789012345" };
+        assert_eq!(script[..2], [OP_PUSHDATA1, 76]);
+        let long = [7u8; 300];
+        let script = bsv_script! { &long };
+        assert_eq!(script[..3], [OP_PUSHDATA2, 0x2c, 0x01]);
+        assert_eq!(script.len(), 303);
+        let slice: &[u8] = &long[..75];
+        assert_eq!(bsv_script! { slice }[0], 75);
+    }
+
+    #[test]
+    fn test_bsv_script_numbers() {
+        let n = 1000i64;
+        let big = i128::MAX;
+        assert_eq!(
+            to_asm(&bsv_script! { n, -1, (n - 1000), 2 + 3, big, OP_ADD }),
+            "0xe803 OP_1NEGATE OP_0 OP_5 0xffffffffffffffffffffffffffffff7f OP_ADD"
+        );
+        assert_eq!(xdrop!(3), vec![0x52, OP_ROLL, 0x75]);
+        let script = bsv_script! { 2, 3, OP_ADD, 5, OP_EQUAL };
+        let mut stack = Stack::default();
+        stack.execute(&script).unwrap();
+        assert_eq!(stack.main, vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_expand_macro_errors() {
+        let pick = def("pick", 1, vec![MacroElem::Param(0), MacroElem::Op(OP_DUP)]);
+        assert_eq!(expand_macro(&pick, &[3]), Ok(vec![0x53, OP_DUP]));
+        assert_eq!(expand_macro(&pick, &[]), Err(err(ArgCountMismatch, "pick")));
+        assert_eq!(
+            expand_macro(&pick, &[1, 2]),
+            Err(err(ArgCountMismatch, "pick"))
+        );
+
+        let bad = def("bad", 1, vec![MacroElem::Param(1)]);
+        assert_eq!(expand_macro(&bad, &[1]), Err(err(ParamOutOfRange, "bad")));
+        // References need a registry
+        let outer = def("outer", 0, vec![call("pick", vec![MacroArg::Const(1)])]);
+        assert_eq!(expand_macro(&outer, &[]), Err(err(UnknownMacro, "pick")));
+        assert_eq!(
+            err(UnknownMacro, "pick").to_string(),
+            "Unknown macro: `pick`"
+        );
+    }
+
+    #[test]
+    fn test_macro_registry() {
+        let mut registry = primitives();
+        // xswap forwards a parameter it does not have
+        assert_eq!(
+            registry.expand("xdrop", &[3]),
+            Err(err(ParamOutOfRange, "xswap"))
+        );
+        registry.insert(def(
+            "xswap",
+            1,
+            vec![
+                call("xroll", vec![MacroArg::Param(0)]),
+                MacroElem::Op(OP_SWAP),
+            ],
+        ));
+        assert_eq!(registry.expand("xrot", &[4]), Ok(xrot!(4)));
+        assert_eq!(
+            registry.expand("xdrop", &[2]),
+            Ok(vec![0x52, OP_ROLL, OP_SWAP, OP_DROP])
+        );
+        assert_eq!(
+            registry.expand("missing", &[]),
+            Err(err(UnknownMacro, "missing"))
+        );
+        assert_eq!(
+            registry.iter().map(|d| d.name.as_str()).collect::<Vec<_>>(),
+            ["xdrop", "xroll", "xrot", "xswap"]
+        );
+
+        // Unregistered definitions resolve references against the registry
+        let drop2 = def(
+            "drop2",
+            0,
+            vec![
+                call("xroll", vec![MacroArg::Const(1)]),
+                MacroElem::Op(OP_DROP),
+            ],
+        );
+        let script = registry.expand_def(&drop2, &[]).unwrap();
+        assert_eq!(script, xdrop!(2));
+        let mut stack = Stack::default();
+        for i in 0..3 {
+            stack.push(vec![i]);
+        }
+        stack.execute(&script).unwrap();
+        assert_eq!(stack.main, vec![vec![0], vec![2]]);
+    }
+
+    #[test]
+    fn test_macro_cycles_and_depth() {
+        let mut registry = primitives();
+        registry.insert(def("a", 0, vec![call("b", vec![])]));
+        registry.insert(def("b", 0, vec![MacroElem::Op(OP_DUP), call("a", vec![])]));
+        assert_eq!(registry.expand("a", &[]), Err(err(Cycle, "a")));
+        assert_eq!(registry.expand("b", &[]), Err(err(Cycle, "b")));
+        registry.insert(def("self", 0, vec![call("self", vec![])]));
+        assert_eq!(registry.expand("self", &[]), Err(err(Cycle, "self")));
+
+        // A chain of 10 distinct macros: m0 -> m1 -> ... -> m9 -> xroll
+        for i in 0..10 {
+            let next = if i == 9 {
+                "xroll".to_string()
+            } else {
+                format!("m{}", i + 1)
+            };
+            registry.insert(def(
+                &format!("m{i}"),
+                1,
+                vec![call(&next, vec![MacroArg::Param(0)])],
+            ));
+        }
+        assert_eq!(registry.expand("m0", &[2]), Ok(vec![0x52, OP_ROLL]));
+        let registry = registry.with_recursion_limit(11);
+        assert!(registry.expand("m0", &[2]).is_ok());
+        let registry = registry.with_recursion_limit(10);
+        assert_eq!(
+            registry.expand("m0", &[2]),
+            Err(err(RecursionLimit, "xroll"))
+        );
+        assert_eq!(registry.expand("m5", &[2]), Ok(vec![0x52, OP_ROLL]));
+    }
+
+    #[test]
+    fn test_std_macro_effects() {
+        let (a, b, c): (&[u8], &[u8], &[u8]) = (&[1], &[2], &[3]);
+        let run3 = |script: Vec<u8>| run(&[a, b, c], &script);
+
+        assert_eq!(run3(xpick!(2)).main, [a, b, c, a]);
+        assert_eq!(run3(xpick!(0)).main, [a, b, c, c]);
+        assert_eq!(run3(xdup!(2)).main, [a, b, c, b, c]);
+        assert_eq!(run3(xdup!(3)).main, [a, b, c, a, b, c]);
+        assert!(xdup!(0).is_empty());
+        assert_eq!(run3(drop_n!(2)).main, [a]);
+        let stack = run3(alt_push_n!(2));
+        assert_eq!(
+            (stack.main, stack.alt),
+            (vec![a.to_vec()], vec![c.to_vec(), b.to_vec()])
+        );
+        let hashed = run(&[b"x"], &hashcat!()).main;
+        assert_eq!((hashed.len(), hashed[0].len()), (1, 21));
+
+        // Endianness flip
+        assert_eq!(
+            run(&[&[1, 2, 3, 4]], &reverse_bytes!(4)).main,
+            [[4, 3, 2, 1]]
+        );
+        assert_eq!(run(&[&[9]], &reverse_bytes!(1)).main, [[9]]);
+
+        // Fixed-width unsigned little-endian
+        let le = |n: i64, width| run(&[&ScriptNum::from(n).encode()], &num2bin_le!(width)).main;
+        assert_eq!(le(0x80, 4), [[0x80, 0, 0, 0]]);
+        assert_eq!(le(0xffff_ffff, 4), [[0xff; 4]]);
+        assert_eq!(le(0, 2), [[0, 0]]);
+
+        // Reverse by prepending each byte, then sum the bytes
+        let script = for_each_byte!(3, { OP_SWAP, OP_CAT });
+        assert_eq!(run(&[&[], b"abc"], &script).main, [b"cba"]);
+        let script = for_each_byte!(3, { OP_ADD });
+        assert_eq!(run(&[&[], &[1, 2, 3]], &script).main, [[6]]);
+        // Bytes past the bound are dropped; too few bytes fail
+        assert_eq!(
+            run(&[&[], &[1, 2, 3]], &for_each_byte!(2, { OP_ADD })).main,
+            [[3]]
+        );
+        let mut stack = Stack::default();
+        stack.push(vec![1]);
+        let err = stack.execute(&for_each_byte!(2, { OP_DROP })).unwrap_err();
+        assert_eq!(err.kind, ScriptErrorKind::InvalidNumberRange);
+
+        assert!(run(&[&[5]], &assert_eq_verify!(5)).main.is_empty());
+        assert!(
+            run(&[&[0xab, 0xcd]], &assert_eq_verify!(hex!("abcd")))
+                .main
+                .is_empty()
+        );
+        let mut stack = Stack::default();
+        stack.push(vec![6]);
+        let err = stack.execute(&assert_eq_verify!(5)).unwrap_err();
+        assert_eq!(err.kind, ScriptErrorKind::VerifyFailed);
+    }
+
+    #[test]
+    fn test_std_macro_defs() {
+        let mut registry = std_macros();
+        registry.insert(def(
+            "each_byte",
+            0,
+            vec![MacroElem::Op(OP_SWAP), MacroElem::Op(OP_CAT)],
+        ));
+        let expand = |name: &str, args: &[i32]| registry.expand(name, args).unwrap();
+        for n in 1..6 {
+            assert_eq!(expand("xswap", &[n]), xswap!(n));
+            assert_eq!(expand("xdrop", &[n]), xdrop!(n));
+            assert_eq!(expand("xrot", &[n]), xrot!(n));
+            assert_eq!(expand("xpick", &[n]), xpick!(n));
+            assert_eq!(expand("xdup", &[n]), xdup!(n));
+            assert_eq!(expand("reverse_bytes", &[n]), reverse_bytes!(n));
+            assert_eq!(expand("num2bin_le", &[n]), num2bin_le!(n));
+            assert_eq!(expand("drop_n", &[n]), drop_n!(n));
+            assert_eq!(expand("alt_push_n", &[n]), alt_push_n!(n));
+            assert_eq!(expand("assert_eq_verify", &[n]), assert_eq_verify!(n));
+            assert_eq!(
+                expand("for_each_byte", &[n]),
+                for_each_byte!(n, { OP_SWAP, OP_CAT })
+            );
+        }
+        assert_eq!(expand("hashcat", &[]), hashcat!());
+        // Its body is the caller's, so for_each_byte cannot declare an effect
+        assert_eq!(registry.get("for_each_byte").unwrap().effect, None);
+
+        // Declared effects hold on a stack of numbers: untouched items below
+        // the inputs, and the declared count of outputs in their place
+        for (name, params) in [
+            ("xswap", 1..=6),
+            ("xdrop", 1..=6),
+            ("xrot", 0..=6),
+            ("xpick", 0..=6),
+            ("xdup", 0..=6),
+            ("drop_n", 0..=6),
+            ("alt_push_n", 0..=6),
+            ("hashcat", 0..=0),
+            ("num2bin_le", 1..=4),
+        ] {
+            let def = registry.get(name).unwrap();
+            for n in params {
+                let args: &[i32] = if def.param_count == 0 { &[] } else { &[n] };
+                let (inputs, outputs) = def.effect.as_ref().unwrap().eval(args).unwrap();
+                let items: Vec<Vec<u8>> = (0..inputs + 2).map(|i| vec![i as u8 + 1]).collect();
+                let items: Vec<&[u8]> = items.iter().map(|i| &i[..]).collect();
+                let stack = run(&items, &expand(name, args));
+                assert_eq!(
+                    stack.main.len() as i32,
+                    2 - inputs + outputs + inputs,
+                    "{name}({n})"
+                );
+                assert_eq!(stack.main[..2], [[1], [2]], "{name}({n})");
+            }
+        }
+        let effect = |name: &str, args: &[i32]| {
+            registry
+                .get(name)
+                .unwrap()
+                .effect
+                .as_ref()
+                .unwrap()
+                .eval(args)
+                .unwrap()
+        };
+        assert_eq!(effect("reverse_bytes", &[4]), (1, 1));
+        assert_eq!(effect("assert_eq_verify", &[4]), (1, 0));
+        assert_eq!(registry.get("for_each_byte").unwrap().effect, None);
+    }
 }
@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::MacroParseErrorKind::*;
+    use nprint_core::{
+        MAX_UNROLL, MacroArg, MacroDef, MacroElem, MacroErrorKind, MacroParseError, MacroRegistry,
+        Stack, StackEffect, expand_macro, parse_macros, xdrop, xrot, xswap,
+    };
+    use sv::script::op_codes::{OP_1SUB, OP_ADD, OP_DROP, OP_DUP, OP_PICK, OP_ROLL, OP_SWAP};
+
+    const LIBRARY: &str = "
+        # Stack shuffling built from ROLL
+        macro xswap(n): $n -> $n { $($n - 1) ROLL }
+        macro xrot(n) { $n OP_ROLL }
+        macro xdrop(n): $n -> $n - 1 { xswap($n) DROP }
+
+        macro twice(a, b) {
+            $a $b ADD -1 ADD   # a + b - 1
+            xrot(2 * ($a - $b) + 1)
+        }
+    ";
+
+    fn parse_err(text: &str) -> (nprint_core::MacroParseErrorKind, usize, usize) {
+        let MacroParseError { kind, line, column } = parse_macros(text).unwrap_err();
+        (kind, line, column)
+    }
+
+    #[test]
+    fn test_parse_macros() {
+        let defs = parse_macros(LIBRARY).unwrap();
+        assert_eq!(
+            defs[0],
+            MacroDef {
+                name: "xswap".into(),
+                param_count: 1,
+                template: vec![
+                    MacroElem::Push(MacroArg::Sub(
+                        Box::new(MacroArg::Param(0)),
+                        Box::new(MacroArg::Const(1))
+                    )),
+                    MacroElem::Op(OP_ROLL),
+                ],
+                effect: Some(StackEffect {
+                    inputs: MacroArg::Param(0),
+                    outputs: MacroArg::Param(0),
+                }),
+            }
+        );
+        assert_eq!(
+            defs[2].template,
+            vec![
+                MacroElem::Macro("xswap".into(), vec![MacroArg::Param(0)]),
+                MacroElem::Op(OP_DROP),
+            ]
+        );
+        assert_eq!(defs[1].effect, None);
+        assert_eq!(defs[2].effect.as_ref().unwrap().eval(&[4]), Ok((4, 3)));
+        assert_eq!(defs[3].param_count, 2);
+        let defs = parse_macros("macro add2(): 2->1 { ADD } macro neg(n):-$n -> -(1) {}").unwrap();
+        assert_eq!(defs[0].effect, Some(StackEffect::fixed(2, 1)));
+        assert_eq!(defs[1].effect.as_ref().unwrap().eval(&[5]), Ok((-5, -1)));
+
+        let defs =
+            parse_macros("macro m(n) { repeat($n - 1) { DUP repeat(2) { ADD } } DROP }").unwrap();
+        assert_eq!(
+            defs[0].template[0],
+            MacroElem::Repeat(
+                MacroArg::Sub(Box::new(MacroArg::Param(0)), Box::new(MacroArg::Const(1))),
+                vec![
+                    MacroElem::Op(OP_DUP),
+                    MacroElem::Repeat(MacroArg::Const(2), vec![MacroElem::Op(OP_ADD)]),
+                ]
+            )
+        );
+        assert_eq!(
+            expand_macro(&defs[0], &[3]),
+            Ok(vec![
+                OP_DUP, OP_ADD, OP_ADD, OP_DUP, OP_ADD, OP_ADD, OP_DROP
+            ])
+        );
+        // Counts below one emit nothing
+        assert_eq!(expand_macro(&defs[0], &[0]), Ok(vec![OP_DROP]));
+        assert_eq!(parse_macros(""), Ok(vec![]));
+        let defs = parse_macros("macro empty() {} macro dup() { OP_DUP DUP 1SUB }").unwrap();
+        assert!(defs[0].template.is_empty());
+        assert_eq!(
+            expand_macro(&defs[1], &[]),
+            Ok(vec![OP_DUP, OP_DUP, OP_1SUB])
+        );
+    }
+
+    #[test]
+    fn test_expand_parsed_macros() {
+        let mut registry = MacroRegistry::new();
+        registry.load_str(LIBRARY).unwrap();
+        for n in 2..6 {
+            assert_eq!(registry.expand("xswap", &[n]), Ok(xswap!(n)));
+            assert_eq!(registry.expand("xrot", &[n]), Ok(xrot!(n)));
+            assert_eq!(registry.expand("xdrop", &[n]), Ok(xdrop!(n)));
+        }
+        // Operator precedence and negative literals
+        let script = registry.expand("twice", &[3, 2]).unwrap();
+        assert_eq!(
+            script,
+            vec![0x53, 0x52, OP_ADD, 0x4f, OP_ADD, 0x53, OP_ROLL]
+        );
+        let mut stack = Stack::default();
+        for i in 0..3 {
+            stack.push(vec![i]);
+        }
+        stack.execute(&script).unwrap();
+        assert_eq!(stack.main, vec![vec![1], vec![2], vec![4], vec![0]]);
+
+        // Arithmetic is checked when expanded
+        assert_eq!(
+            registry.expand("xswap", &[i32::MIN]).unwrap_err().kind,
+            MacroErrorKind::ArithmeticOverflow
+        );
+        // Repeat counts are capped like loop_unroll! bounds
+        registry
+            .load_str("macro nops(n) { repeat($n) { NOP } }")
+            .unwrap();
+        let nops = registry.expand("nops", &[MAX_UNROLL as i32]).unwrap();
+        assert_eq!(nops.len(), MAX_UNROLL);
+        let err = registry
+            .expand("nops", &[MAX_UNROLL as i32 + 1])
+            .unwrap_err();
+        assert_eq!(
+            (err.kind, err.name.as_str()),
+            (MacroErrorKind::RepeatLimit, "nops")
+        );
+        assert_eq!(err.to_string(), "Repeat count too large: `nops`");
+        // Later text replaces earlier definitions
+        registry.load_str("macro xrot(n) { $n PICK SWAP }").unwrap();
+        assert_eq!(
+            registry.expand("xrot", &[1]),
+            Ok(vec![0x51, OP_PICK, OP_SWAP])
+        );
+        // A failed load adds nothing
+        assert!(registry.load_str("macro a() {} macro b() { FOO }").is_err());
+        assert!(!registry.contains("a"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_err("macro m() { OP_FOO }"), (UnknownOpcode, 1, 13));
+        assert_eq!(
+            parse_err("macro m(n) {\n  $n\n  $x\n}"),
+            (UnknownParam, 3, 4)
+        );
+        assert_eq!(parse_err("macro m(n, n) {}"), (DuplicateParam, 1, 12));
+        assert_eq!(
+            parse_err("macro m() {}\nmacro m() {}"),
+            (DuplicateMacro, 2, 1)
+        );
+        assert_eq!(
+            parse_err("macro m() { 2147483648 }"),
+            (InvalidNumber, 1, 13)
+        );
+        assert_eq!(parse_err("macro m() { $(1 + ) }"), (UnexpectedToken, 1, 19));
+        assert_eq!(parse_err("macro m() { ADD"), (UnexpectedEnd, 1, 16));
+        assert_eq!(parse_err("macro 1m() {}"), (UnexpectedToken, 1, 7));
+        assert_eq!(parse_err("func m() {}"), (UnexpectedToken, 1, 1));
+        assert_eq!(parse_err("macro m() { 0x01 }"), (UnknownOpcode, 1, 13));
+        assert_eq!(parse_err("macro m() { ; }"), (UnexpectedToken, 1, 13));
+        assert_eq!(parse_err("macro m(): 1 2 { }"), (UnexpectedToken, 1, 14));
+        assert_eq!(parse_err("macro m(a): $b -> 1 { }"), (UnknownParam, 1, 14));
+        assert_eq!(parse_err("macro m() { 1 -> 2 }"), (UnexpectedToken, 1, 15));
+        assert!(parse_macros("macro m() { -2147483648 }").is_ok());
+        assert_eq!(
+            parse_macros("macro m() {").unwrap_err().to_string(),
+            "Unexpected end of input at line 1, column 12"
+        );
+    }
+}
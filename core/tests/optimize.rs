@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::{
+        OptLevel, RULES, bsv_script, check_equivalence, optimize, optimize_checked, optimize_with,
+    };
+    use proptest::prelude::*;
+    use sv::script::op_codes::{OP_IF, OP_NOTIF};
+
+    /// Makes a rule's pattern or replacement a complete script.
+    fn close_branches(ops: &[u8]) -> Vec<u8> {
+        let mut script = ops.to_vec();
+        if ops.iter().any(|op| [OP_IF, OP_NOTIF].contains(op)) {
+            script.extend(bsv_script! { OP_1, OP_ELSE, OP_2, OP_ENDIF });
+        }
+        script
+    }
+
+    #[test]
+    fn test_optimize_rewrites() {
+        let cases = [
+            (bsv_script! { OP_1, OP_ROLL }, bsv_script! { OP_SWAP }),
+            (bsv_script! { OP_0, OP_PICK }, bsv_script! { OP_DUP }),
+            (
+                bsv_script! { OP_EQUAL, OP_VERIFY },
+                bsv_script! { OP_EQUALVERIFY },
+            ),
+            (bsv_script! { OP_1, OP_ADD }, bsv_script! { OP_1ADD }),
+            (
+                bsv_script! { OP_NOP, OP_DROP, OP_NOP, OP_DROP },
+                bsv_script! { OP_2DROP },
+            ),
+        ];
+        for (script, expected) in cases {
+            assert_eq!(optimize(&script), expected);
+        }
+
+        // Deletions and OP_NOT folding need the aggressive level
+        let script =
+            bsv_script! { OP_0, OP_ROLL, OP_DUP, OP_DROP, OP_SWAP, OP_SWAP, OP_NOT, OP_IF };
+        assert_eq!(optimize(&script), script);
+        assert_eq!(
+            optimize_with(&script, OptLevel::Aggressive),
+            bsv_script! { OP_NOTIF }
+        );
+        assert_eq!(optimize_with(&script, OptLevel::None), script);
+
+        // Removing the inner pair exposes the outer one
+        let script = bsv_script! { OP_SWAP, OP_DUP, OP_DROP, OP_SWAP, OP_1 };
+        assert_eq!(
+            optimize_with(&script, OptLevel::Aggressive),
+            bsv_script! { OP_1 }
+        );
+    }
+
+    #[test]
+    fn test_optimize_leaves_data_alone() {
+        // Push data spelling OP_1 OP_ROLL
+        let script = bsv_script! { hex!("517a"), OP_DROP };
+        assert_eq!(optimize(&script), script);
+        // A pushed OP_1 byte is not OP_1
+        let script = [0x01, 0x01, 0x7a];
+        assert_eq!(optimize(&script), script);
+
+        // Nothing from OP_RETURN on is rewritten, nor a truncated push
+        let script = bsv_script! { OP_1, OP_ROLL, OP_RETURN, OP_1, OP_ROLL };
+        assert_eq!(
+            optimize(&script),
+            bsv_script! { OP_SWAP, OP_RETURN, OP_1, OP_ROLL }
+        );
+        let script = [0x51, 0x7a, 0x4c];
+        assert_eq!(optimize(&script), [0x7c, 0x4c]);
+    }
+
+    #[test]
+    fn test_rule_levels() {
+        for rule in RULES {
+            let pattern = close_branches(rule.pattern);
+            let replacement = close_branches(rule.replacement);
+            assert!(
+                check_equivalence(&pattern, &replacement, rule.level, 500, 7).is_ok(),
+                "{rule:?}"
+            );
+            // Aggressive rules really do change some failing inputs
+            if rule.level == OptLevel::Aggressive {
+                assert!(
+                    check_equivalence(&pattern, &replacement, OptLevel::Basic, 500, 7).is_err(),
+                    "{rule:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_equivalence() {
+        let original = bsv_script! { OP_2, OP_ROLL };
+        let err = check_equivalence(&original, &bsv_script! { OP_SWAP }, OptLevel::Basic, 100, 3)
+            .unwrap_err();
+        assert!(err.inputs.len() >= 2);
+        assert!(
+            err.to_string()
+                .starts_with("Scripts disagree on input stack")
+        );
+        // The same inputs always come from the same seed
+        let again = check_equivalence(&original, &bsv_script! { OP_SWAP }, OptLevel::Basic, 100, 3);
+        assert_eq!(again, Err(err));
+        assert!(check_equivalence(&original, &original, OptLevel::Basic, 0, 3).is_ok());
+    }
+
+    fn script_strategy() -> impl Strategy<Value = Vec<u8>> {
+        let ops = prop::sample::select(bsv_script! {
+            OP_0, OP_1, OP_2, OP_3, OP_PICK, OP_ROLL, OP_DUP, OP_DROP, OP_SWAP, OP_ROT,
+            OP_OVER, OP_EQUAL, OP_NUMEQUAL, OP_VERIFY, OP_ADD, OP_SUB, OP_NEGATE, OP_NOT,
+            OP_NOP, OP_TOALTSTACK, OP_FROMALTSTACK, OP_IF, OP_NOTIF, OP_ELSE, OP_ENDIF,
+        });
+        prop::collection::vec(ops, 0..24)
+    }
+
+    proptest! {
+        #[test]
+        fn test_optimize_checked(script in script_strategy()) {
+            for level in [OptLevel::Basic, OptLevel::Aggressive] {
+                let optimized = optimize_checked(&script, level, 200);
+                prop_assert!(optimized.is_ok(), "{:?} at {:?}: {:?}", script, level, optimized);
+                prop_assert!(optimized.unwrap().len() <= script.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::{
+        Instruction, Instructions, PushKind, ScriptNum, Stack, encode_push, parse_script,
+    };
+    use proptest::prelude::*;
+    use sv::script::op_codes::{
+        OP_0, OP_1, OP_1NEGATE, OP_16, OP_CHECKSIG, OP_DUP, OP_PUSHDATA1, OP_PUSHDATA2,
+        OP_PUSHDATA4,
+    };
+
+    fn parse(script: &[u8]) -> Vec<Instruction<'_>> {
+        let (rest, instructions) = parse_script(script).unwrap();
+        assert!(rest.is_empty());
+        instructions
+    }
+
+    fn push(kind: PushKind, data: &[u8]) -> Instruction<'_> {
+        Instruction::Push { kind, data }
+    }
+
+    proptest! {
+        #[test]
+        fn test_instructions_cover_script(script in prop::collection::vec(any::<u8>(), 0..200)) {
+            let mut expected = 0;
+            for (offset, instruction) in Instructions::new(&script) {
+                prop_assert_eq!(offset, expected);
+                prop_assert_eq!(instruction.opcode(), script[offset]);
+                if let Instruction::Push { data, .. } = instruction {
+                    // Payloads borrow from the script
+                    let end = offset + instruction.len();
+                    prop_assert_eq!(data.as_ptr_range().end, script[end..].as_ptr());
+                }
+                expected += instruction.len();
+            }
+            prop_assert_eq!(expected, script.len());
+        }
+
+        #[test]
+        fn test_number_pushes(n in any::<i64>()) {
+            let script = ScriptNum::from(n).to_push();
+            let instructions = parse(&script);
+            prop_assert_eq!(instructions.len(), 1);
+            let encoded = ScriptNum::from(n).encode();
+            match instructions[0] {
+                Instruction::Op(op) => prop_assert!(op == OP_0 || (OP_1..=OP_16).contains(&op) || n == -1),
+                Instruction::Push { kind, data } => {
+                    prop_assert_eq!(kind, PushKind::Direct);
+                    prop_assert_eq!(data, &encoded[..]);
+                }
+                Instruction::Invalid(_) => prop_assert!(false),
+            }
+        }
+
+        #[test]
+        fn test_encode_push_executes(data in prop::collection::vec(any::<u8>(), 0..600), minimal: bool) {
+            let script = encode_push(&data, minimal);
+            prop_assert_eq!(parse(&script).len(), 1);
+            let mut stack = Stack::default();
+            stack.execute(&script).unwrap();
+            prop_assert_eq!(stack.main, vec![data]);
+        }
+    }
+
+    #[test]
+    fn test_encode_push_lengths() {
+        for (len, prefix) in [
+            (0, &[OP_0][..]),
+            (1, &[1]),
+            (75, &[75]),
+            (76, &[OP_PUSHDATA1, 76]),
+            (255, &[OP_PUSHDATA1, 255]),
+            (256, &[OP_PUSHDATA2, 0, 1]),
+            (65535, &[OP_PUSHDATA2, 255, 255]),
+            (65536, &[OP_PUSHDATA4, 0, 0, 1, 0]),
+        ] {
+            let data = vec![0xaa; len];
+            for minimal in [false, true] {
+                let script = encode_push(&data, minimal);
+                assert_eq!(&script[..prefix.len()], prefix, "length {len}");
+                assert_eq!(&script[prefix.len()..], &data[..]);
+            }
+        }
+
+        // Only the minimal mode substitutes number opcodes
+        for (byte, op) in [(1, OP_1), (16, OP_16), (0x81, OP_1NEGATE)] {
+            assert_eq!(encode_push(&[byte], true), vec![op]);
+            assert_eq!(encode_push(&[byte], false), vec![1, byte]);
+        }
+        assert_eq!(encode_push(&[0], true), vec![1, 0]);
+        assert_eq!(encode_push(&[17], true), vec![1, 17]);
+    }
+
+    #[test]
+    fn test_parse_script() {
+        assert_eq!(parse(&[]), vec![]);
+        let script = [
+            &[OP_0, OP_DUP, 2, 0xaa, 0xbb][..],
+            &[OP_PUSHDATA1, 1, 0xcc],
+            &[OP_PUSHDATA2, 2, 0, 0xdd, 0xee],
+            &[OP_PUSHDATA4, 1, 0, 0, 0, 0xff],
+            &[OP_CHECKSIG, 0xff],
+        ]
+        .concat();
+        assert_eq!(
+            parse(&script),
+            vec![
+                push(PushKind::Direct, &[]),
+                Instruction::Op(OP_DUP),
+                push(PushKind::Direct, &[0xaa, 0xbb]),
+                push(PushKind::PushData1, &[0xcc]),
+                push(PushKind::PushData2, &[0xdd, 0xee]),
+                push(PushKind::PushData4, &[0xff]),
+                Instruction::Op(OP_CHECKSIG),
+                Instruction::Op(0xff),
+            ]
+        );
+        let lens: Vec<_> = parse(&script).iter().map(|i| i.len()).collect();
+        assert_eq!(lens, vec![1, 1, 3, 3, 5, 6, 1, 1]);
+    }
+
+    #[test]
+    fn test_truncated_pushes() {
+        for script in [
+            &[3, 1, 2][..],
+            &[OP_PUSHDATA1],
+            &[OP_PUSHDATA1, 2, 1],
+            &[OP_PUSHDATA2, 1],
+            &[OP_PUSHDATA2, 0, 1],
+            &[OP_PUSHDATA4, 0, 0, 0],
+            &[OP_PUSHDATA4, 0xff, 0xff, 0xff, 0xff, 1],
+        ] {
+            assert_eq!(parse(script), vec![Instruction::Invalid(script)]);
+        }
+        // Instructions before the truncated push are kept
+        assert_eq!(
+            parse(&[OP_DUP, OP_PUSHDATA1, 9, OP_DUP]),
+            vec![
+                Instruction::Op(OP_DUP),
+                Instruction::Invalid(&[OP_PUSHDATA1, 9, OP_DUP]),
+            ]
+        );
+        assert_eq!(
+            Instruction::Invalid(&[OP_PUSHDATA2, 1]).opcode(),
+            OP_PUSHDATA2
+        );
+    }
+}
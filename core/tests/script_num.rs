@@ -0,0 +1,137 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::ScriptErrorKind::*;
+    use nprint_core::{
+        MacroDef, MacroElem, ScriptFlags, ScriptNum, Stack, bsv_script, expand_macro,
+    };
+    use num_bigint::BigInt;
+    use proptest::prelude::*;
+    use sv::script::op_codes::{
+        OP_1NEGATE, OP_ADD, OP_DIV, OP_FALSE, OP_MOD, OP_MUL, OP_NUMEQUAL, OP_PUSHDATA1, OP_SUB,
+    };
+
+    fn eval(
+        flags: ScriptFlags,
+        script: &[u8],
+    ) -> Result<Vec<Vec<u8>>, nprint_core::ScriptErrorKind> {
+        let mut stack = Stack {
+            flags,
+            ..Default::default()
+        };
+        stack.execute(script).map_err(|e| e.kind)?;
+        Ok(stack.main)
+    }
+
+    proptest! {
+        #[test]
+        fn test_i128_roundtrip(n in any::<i128>()) {
+            let num = ScriptNum::from(n);
+            prop_assert_eq!(ScriptNum::decode(&num.encode(), 17, true), Ok(num));
+        }
+
+        #[test]
+        fn test_push_executes_to_encoding(n in any::<i128>()) {
+            let num = ScriptNum::from(n);
+            let stack = eval(ScriptFlags::GENESIS_CONSENSUS, &num.to_push()).unwrap();
+            prop_assert_eq!(stack, vec![num.encode()]);
+        }
+
+        #[test]
+        fn test_arithmetic_matches_i128(a in any::<i64>(), b in any::<i64>()) {
+            let (wa, wb) = (a as i128, b as i128);
+            for (op, expected) in [(OP_ADD, wa + wb), (OP_SUB, wa - wb), (OP_MUL, wa * wb)] {
+                let script = [
+                    ScriptNum::from(a).to_push(),
+                    ScriptNum::from(b).to_push(),
+                    vec![op],
+                ]
+                .concat();
+                let stack = eval(ScriptFlags::GENESIS_CONSENSUS, &script).unwrap();
+                prop_assert_eq!(&stack[0], &ScriptNum::from(expected).encode());
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoding_edges() {
+        assert_eq!(ScriptNum::from(0).encode(), Vec::<u8>::new());
+        assert_eq!(ScriptNum::from(127).encode(), vec![0x7f]);
+        assert_eq!(ScriptNum::from(128).encode(), vec![0x80, 0x00]);
+        assert_eq!(ScriptNum::from(-128).encode(), vec![0x80, 0x80]);
+        assert_eq!(
+            ScriptNum::from(u64::MAX).encode(),
+            [[0xff; 8].as_slice(), &[0]].concat()
+        );
+        assert_eq!(
+            ScriptNum::decode(&[0x00, 0x80], 2, false),
+            Ok(ScriptNum::from(0))
+        );
+        assert_eq!(
+            ScriptNum::decode(&[0x00, 0x80], 2, true),
+            Err(NonMinimalNumber)
+        );
+        assert_eq!(ScriptNum::decode(&[1, 2, 3], 2, true), Err(NumberOverflow));
+        assert_eq!(ScriptNum::from(-1).to_push(), vec![OP_1NEGATE]);
+        assert_eq!(ScriptNum::from(0).to_push(), vec![OP_FALSE]);
+        let big = ScriptNum::from(BigInt::from(1) << 2000);
+        assert_eq!(big.to_push()[..2], [OP_PUSHDATA1, 251]);
+        assert_eq!(big.to_string().len(), 603);
+    }
+
+    #[test]
+    fn test_post_genesis_big_arithmetic() {
+        // (2^100 * 3) / 2^100 == 3 and (2^100 + 5) % 2^100 == 5
+        let p = ScriptNum::from(BigInt::from(1) << 100);
+        let script = [
+            p.to_push(),
+            bsv_script! { 3 },
+            vec![OP_MUL],
+            p.to_push(),
+            vec![OP_DIV],
+            bsv_script! { 3 },
+            vec![OP_NUMEQUAL],
+        ]
+        .concat();
+        assert_eq!(
+            eval(ScriptFlags::GENESIS_CONSENSUS, &script).unwrap(),
+            vec![vec![1]]
+        );
+        // OP_MUL itself is disabled before Genesis
+        assert_eq!(eval(ScriptFlags::PRE_GENESIS, &script), Err(DisabledOpcode));
+
+        let plus_five = ScriptNum::from((BigInt::from(1) << 100) + 5);
+        let script = [plus_five.to_push(), p.to_push(), vec![OP_MOD]].concat();
+        assert_eq!(
+            eval(ScriptFlags::GENESIS_CONSENSUS, &script).unwrap(),
+            vec![vec![5]]
+        );
+        assert_eq!(eval(ScriptFlags::PRE_GENESIS, &script), Err(NumberOverflow));
+
+        // Operand length is still bounded by the rule set
+        let flags = ScriptFlags {
+            max_num_size: 12,
+            ..ScriptFlags::GENESIS_CONSENSUS
+        };
+        assert_eq!(
+            eval(flags, &[p.to_push(), vec![OP_1NEGATE, OP_ADD]].concat()),
+            Err(NumberOverflow)
+        );
+    }
+
+    #[test]
+    fn test_large_constants_in_macros() {
+        let n = i128::MAX;
+        assert_eq!(bsv_script! { n }, ScriptNum::from(n).to_push());
+        assert_eq!(bsv_script! { 0 }, vec![OP_FALSE]);
+        let def = MacroDef {
+            name: "push".to_string(),
+            param_count: 1,
+            template: vec![MacroElem::Param(0)],
+            effect: None,
+        };
+        assert_eq!(
+            expand_macro(&def, &[i32::MIN]).unwrap(),
+            ScriptNum::from(i32::MIN).to_push()
+        );
+    }
+}
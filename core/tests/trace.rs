@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::ScriptErrorKind::*;
+    use nprint_core::{
+        Breakpoint, Halt, Instruction, Interpreter, PushKind, ScriptFlags, Snapshot, Stack,
+        from_asm,
+    };
+    use serde_json::{Value, json};
+    use sv::script::op_codes::{OP_ADD, OP_DUP, OP_ENDIF, OP_IF, OP_RETURN};
+
+    fn script(asm: &str) -> Vec<u8> {
+        from_asm(asm).unwrap()
+    }
+
+    #[test]
+    fn test_step() {
+        let script = script("OP_1 0x0203 OP_TOALTSTACK OP_0 OP_IF OP_DUP OP_ENDIF");
+        let mut stack = Stack::default();
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        assert_eq!(
+            interpreter.next_instruction(),
+            Some((0, Instruction::Op(0x51)))
+        );
+
+        let step = interpreter.step().unwrap().unwrap();
+        assert_eq!((step.offset, step.executed), (0, true));
+        let step = interpreter.step().unwrap().unwrap();
+        assert_eq!(
+            step.instruction,
+            Instruction::Push {
+                kind: PushKind::Direct,
+                data: &[2, 3]
+            }
+        );
+        interpreter.step().unwrap();
+        assert_eq!(
+            interpreter.snapshot(),
+            Snapshot {
+                main: vec![vec![1]],
+                alt: vec![vec![2, 3]],
+                cond: vec![],
+            }
+        );
+        interpreter.step().unwrap();
+        interpreter.step().unwrap();
+        assert_eq!(interpreter.snapshot().cond, vec![false]);
+        // Instructions in unexecuted branches are still stepped over
+        let step = interpreter.step().unwrap().unwrap();
+        assert_eq!((step.offset, step.executed), (7, false));
+        interpreter.step().unwrap();
+        assert!(!interpreter.is_finished());
+        assert_eq!(interpreter.step(), Ok(None));
+        assert!(interpreter.is_finished());
+        assert_eq!(interpreter.step(), Ok(None));
+        assert_eq!(stack.main, vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let script = script("OP_1 OP_DUP OP_ADD OP_DUP OP_ADD OP_DUP");
+        let mut stack = Stack::default();
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        interpreter.add_breakpoint(Breakpoint::Opcode(OP_ADD));
+        interpreter.add_breakpoint(Breakpoint::Offset(5));
+        assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 2 }));
+        assert_eq!(interpreter.stack().main, vec![vec![1], vec![1]]);
+        assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 4 }));
+        assert_eq!(interpreter.stack().main, vec![vec![2], vec![2]]);
+        assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 5 }));
+        interpreter.remove_breakpoint(Breakpoint::Opcode(OP_ADD));
+        assert_eq!(interpreter.resume(), Ok(Halt::Finished));
+        assert_eq!(interpreter.resume(), Ok(Halt::Finished));
+        assert_eq!(stack.main, vec![vec![4], vec![4]]);
+    }
+
+    #[test]
+    fn test_breakpoint_first_instruction() {
+        let script = script("OP_1 OP_DUP");
+        let mut stack = Stack::default();
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        interpreter.add_breakpoint(Breakpoint::Offset(0));
+        assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 0 }));
+        assert!(interpreter.stack().main.is_empty());
+        assert_eq!(interpreter.resume(), Ok(Halt::Finished));
+        assert_eq!(stack.main, vec![vec![1], vec![1]]);
+
+        // Likewise for an opcode breakpoint, and only once per interpreter
+        let script = [0x51, 0x51, OP_ADD];
+        let mut stack = Stack::default();
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        interpreter.add_breakpoint(Breakpoint::Opcode(0x51));
+        assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 0 }));
+        assert_eq!(interpreter.resume(), Ok(Halt::Breakpoint { offset: 1 }));
+        assert_eq!(interpreter.resume(), Ok(Halt::Finished));
+    }
+
+    #[test]
+    fn test_step_errors() {
+        let script = [OP_ADD, OP_DUP];
+        let mut stack = Stack::default();
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        interpreter.add_breakpoint(Breakpoint::Opcode(OP_DUP));
+        let err = interpreter.resume().unwrap_err();
+        assert_eq!((err.kind, err.offset), (StackUnderflow, 0));
+        assert!(interpreter.is_finished());
+        assert_eq!(interpreter.next_instruction(), None);
+        assert_eq!(interpreter.step(), Ok(None));
+
+        // An open conditional fails on the step after the last instruction
+        let script = [OP_IF];
+        let mut stack = Stack::default();
+        stack.push(vec![1]);
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        assert!(interpreter.step().unwrap().is_some());
+        let err = interpreter.step().unwrap_err();
+        assert_eq!(
+            (err.kind, err.opcode, err.offset),
+            (UnbalancedConditional, OP_ENDIF, 1)
+        );
+
+        // A top-level OP_RETURN after Genesis ends execution successfully
+        let script = [OP_RETURN, 0xff];
+        let mut stack = Stack::default();
+        let mut interpreter = Interpreter::new(&mut stack, &script);
+        assert!(interpreter.step().unwrap().is_some());
+        assert!(interpreter.is_finished());
+        assert_eq!(interpreter.resume(), Ok(Halt::Finished));
+
+        let mut stack = Stack {
+            flags: ScriptFlags::PRE_GENESIS,
+            ..Default::default()
+        };
+        let err = Interpreter::new(&mut stack, &script).step().unwrap_err();
+        assert_eq!(err.kind, EarlyReturn);
+    }
+
+    #[test]
+    fn test_trace_json() {
+        let script = script("OP_2 OP_PUSHDATA1 0x03 OP_TOALTSTACK OP_1 OP_IF OP_ADD OP_ENDIF");
+        // Consensus rules allow the non-minimal push
+        let mut stack = Stack {
+            flags: ScriptFlags::GENESIS_CONSENSUS,
+            ..Default::default()
+        };
+        let trace = Interpreter::new(&mut stack, &script).trace();
+        assert_eq!(trace.steps.len(), 5);
+        assert_eq!(trace.error.map(|e| e.kind), Some(StackUnderflow));
+        let json: Value = serde_json::from_str(&trace.to_json()).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "steps": [
+                    {"offset": 0, "asm": "OP_2", "executed": true, "main": ["02"], "alt": [], "cond": []},
+                    {"offset": 1, "asm": "OP_PUSHDATA1 0x03", "executed": true, "main": ["02", "03"], "alt": [], "cond": []},
+                    {"offset": 4, "asm": "OP_TOALTSTACK", "executed": true, "main": ["02"], "alt": ["03"], "cond": []},
+                    {"offset": 5, "asm": "OP_1", "executed": true, "main": ["02", "01"], "alt": ["03"], "cond": []},
+                    {"offset": 6, "asm": "OP_IF", "executed": true, "main": ["02"], "alt": ["03"], "cond": [true]},
+                ],
+                "error": {"kind": "StackUnderflow", "opcode": OP_ADD, "offset": 7},
+            })
+        );
+
+        let mut stack = Stack::default();
+        let trace = Interpreter::new(&mut stack, &[]).trace();
+        assert_eq!(trace.to_json(), r#"{"steps":[],"error":null}"#);
+    }
+}
@@ -2,6 +2,8 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{ItemStruct, parse_macro_input};
 
+mod script;
+
 #[proc_macro_derive(SmartContract)]
 pub fn smart_contract_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as ItemStruct);
@@ -26,3 +28,48 @@ pub fn smart_contract_derive(input: TokenStream) -> TokenStream {
 
     compile.into()
 }
+
+/// Builds a script at compile time, checking its stack effects.
+///
+/// Items are the same as `nprint_core::bsv_script!`: opcode names,
+/// `hex!("...")` and `b"..."` data pushes, integers and other macro calls.
+/// A script made only of the first three is emitted as a `[u8; N]` array,
+/// usable in a `const`. Otherwise the macro evaluates to a `Vec<u8>`: other
+/// expressions are pushed at run time through `nprint_core::ScriptLiteral`,
+/// and the scripts that macro calls such as `xswap!(3)` return are spliced
+/// in.
+///
+/// Conditionals must balance. With an `inputs = N;` header the script is
+/// also executed symbolically against `N` stack items, and an opcode that
+/// would underflow the main or alt stack is a compile error pointing at it.
+/// Literal counts for OP_PICK, OP_ROLL and OP_CHECKMULTISIG are followed;
+/// after a depth that depends on run-time data, or a spliced macro call,
+/// checking stops.
+///
+/// ```
+/// use nprint_dsl::bsv_script;
+///
+/// const SWAP_ADD: [u8; 2] = bsv_script! { inputs = 2; OP_SWAP, OP_ADD };
+/// assert_eq!(SWAP_ADD, [0x7c, 0x93]);
+///
+/// let pkh = [0u8; 20];
+/// let p2pkh = bsv_script! { inputs = 2; OP_DUP, OP_HASH160, &pkh, OP_EQUALVERIFY, OP_CHECKSIG };
+/// assert_eq!(p2pkh.len(), 25);
+/// ```
+///
+/// ```compile_fail
+/// // OP_SWAP needs 2 stack items but only 1 is available (inputs = 1)
+/// nprint_dsl::bsv_script! { inputs = 1; OP_SWAP };
+/// ```
+///
+/// ```compile_fail
+/// // OP_IF without OP_ENDIF
+/// nprint_dsl::bsv_script! { OP_IF, OP_1 };
+/// ```
+#[proc_macro]
+pub fn bsv_script(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as script::ScriptInput);
+    script::expand(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
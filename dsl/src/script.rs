@@ -0,0 +1,474 @@
+//! Compile-time `bsv_script!`: parses the items, checks stack effects and
+//! emits the script bytes.
+
+use nprint_core::{ScriptNum, encode_push, opcode_from_name, opcode_name};
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Error, Expr, ExprLit, ExprUnary, Ident, Lit, LitInt, LitStr, Result, Token, UnOp};
+
+/// Macro input: an optional `inputs = N;` header and comma-separated items.
+pub struct ScriptInput {
+    inputs: Option<usize>,
+    items: Punctuated<Expr, Token![,]>,
+}
+
+impl Parse for ScriptInput {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut inputs = None;
+        let fork = input.fork();
+        if fork.parse::<Ident>().is_ok_and(|i| i == "inputs") && fork.peek(Token![=]) {
+            input.parse::<Ident>()?;
+            input.parse::<Token![=]>()?;
+            inputs = Some(input.parse::<LitInt>()?.base10_parse()?);
+            input.parse::<Token![;]>()?;
+        }
+        let items = Punctuated::parse_terminated(input)?;
+        Ok(Self { inputs, items })
+    }
+}
+
+/// A classified script item.
+enum Item {
+    Op(u8),
+    /// Push known at compile time, with its numeric value when it is a number.
+    Push(Vec<u8>, Option<i64>),
+    /// Expression pushed at run time.
+    Runtime(Expr),
+    /// Macro call whose script is spliced in at run time.
+    Splice(Expr),
+}
+
+fn classify(expr: &Expr) -> Result<Item> {
+    match expr {
+        Expr::Group(group) => classify(&group.expr),
+        Expr::Path(path) if path.qself.is_none() => {
+            let Some(ident) = path.path.get_ident() else {
+                return Ok(Item::Runtime(expr.clone()));
+            };
+            let name = ident.to_string();
+            if !name.starts_with("OP_") {
+                return Ok(Item::Runtime(expr.clone()));
+            }
+            opcode_from_name(&name)
+                .map(Item::Op)
+                .ok_or_else(|| Error::new(ident.span(), format!("unknown opcode `{name}`")))
+        }
+        Expr::Macro(mac) if mac.mac.path.is_ident("hex") => {
+            let lit: LitStr = mac.mac.parse_body()?;
+            let data = decode_hex(&lit.value())
+                .ok_or_else(|| Error::new(lit.span(), "invalid hex literal"))?;
+            Ok(Item::Push(encode_push(&data, true), None))
+        }
+        Expr::Macro(_) => Ok(Item::Splice(expr.clone())),
+        Expr::Lit(ExprLit { lit, .. }) => match lit {
+            Lit::Int(int) => number(int.base10_parse()?),
+            Lit::ByteStr(bytes) => Ok(Item::Push(encode_push(&bytes.value(), true), None)),
+            Lit::Str(_) => Err(Error::new(
+                lit.span(),
+                "string literals are not pushable; use a byte string b\"...\"",
+            )),
+            _ => Err(Error::new(lit.span(), "unsupported literal")),
+        },
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr: inner,
+            ..
+        }) => match &**inner {
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(int), ..
+            }) => number(-int.base10_parse::<i128>()?),
+            _ => Ok(Item::Runtime(expr.clone())),
+        },
+        _ => Ok(Item::Runtime(expr.clone())),
+    }
+}
+
+fn number(n: i128) -> Result<Item> {
+    Ok(Item::Push(
+        ScriptNum::from(n).to_push(),
+        i64::try_from(n).ok(),
+    ))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// How an opcode changes the stacks.
+enum Effect {
+    /// Pops and pushes a fixed number of main stack items.
+    Fixed(usize, usize),
+    If,
+    Else,
+    EndIf,
+    Return,
+    Pick,
+    Roll,
+    Depth,
+    IfDup,
+    ToAlt,
+    FromAlt,
+    /// OP_CHECKMULTISIG (1 result) or OP_CHECKMULTISIGVERIFY (none).
+    Multisig(usize),
+}
+
+fn effect(op: u8) -> Effect {
+    use Effect::*;
+    match op {
+        0x00..=0x4e | 0x4f | 0x51..=0x60 => Fixed(0, 1),
+        0x63 | 0x64 => If,
+        0x67 => Else,
+        0x68 => EndIf,
+        0x69 => Fixed(1, 0),
+        0x6a => Return,
+        0x6b => ToAlt,
+        0x6c => FromAlt,
+        0x6d => Fixed(2, 0),
+        0x6e => Fixed(2, 4),
+        0x6f => Fixed(3, 6),
+        0x70 => Fixed(4, 6),
+        0x71 => Fixed(6, 6),
+        0x72 => Fixed(4, 4),
+        0x73 => IfDup,
+        0x74 => Depth,
+        0x75 => Fixed(1, 0),
+        0x76 => Fixed(1, 2),
+        0x77 => Fixed(2, 1),
+        0x78 => Fixed(2, 3),
+        0x79 => Pick,
+        0x7a => Roll,
+        0x7b => Fixed(3, 3),
+        0x7c => Fixed(2, 2),
+        0x7d => Fixed(2, 3),
+        0x7e => Fixed(2, 1),
+        0x7f => Fixed(2, 2),
+        0x80 => Fixed(2, 1),
+        0x81 => Fixed(1, 1),
+        0x82 => Fixed(1, 2),
+        0x83 => Fixed(1, 1),
+        0x84..=0x87 => Fixed(2, 1),
+        0x88 => Fixed(2, 0),
+        0x8b..=0x92 => Fixed(1, 1),
+        0x93..=0x9c => Fixed(2, 1),
+        0x9d => Fixed(2, 0),
+        0x9e..=0xa4 => Fixed(2, 1),
+        0xa5 => Fixed(3, 1),
+        0xa6..=0xaa => Fixed(1, 1),
+        0xac => Fixed(2, 1),
+        0xad => Fixed(2, 0),
+        0xae => Multisig(1),
+        0xaf => Multisig(0),
+        // CHECKLOCKTIMEVERIFY and CHECKSEQUENCEVERIFY inspect the top item
+        0xb1 | 0xb2 => Fixed(1, 1),
+        _ => Fixed(0, 0),
+    }
+}
+
+/// Stacks as far as they are known at one point of the script.
+#[derive(Clone, PartialEq)]
+struct State {
+    /// False after an OP_RETURN.
+    reachable: bool,
+    /// Exact main and alt depths, when known.
+    depth: Option<usize>,
+    alt: Option<usize>,
+    /// Values of the topmost items, when they are literal numbers.
+    values: Vec<Option<i64>>,
+}
+
+struct Frame {
+    span: Span,
+    start: State,
+    ends: Vec<State>,
+    has_else: bool,
+}
+
+/// Tracks stack depths through the script, reporting underflows and
+/// unbalanced conditionals.
+struct Checker {
+    state: State,
+    frames: Vec<Frame>,
+    inputs: Option<usize>,
+}
+
+impl Checker {
+    fn new(inputs: Option<usize>) -> Self {
+        Self {
+            state: State {
+                reachable: true,
+                depth: inputs,
+                alt: inputs.map(|_| 0),
+                values: Vec::new(),
+            },
+            frames: Vec::new(),
+            inputs,
+        }
+    }
+
+    fn require(&self, n: usize, op: u8, span: Span) -> Result<()> {
+        match self.state.depth {
+            Some(depth) if self.state.reachable && depth < n => {
+                let name = opcode_name(op).unwrap_or("opcode");
+                let inputs = self.inputs.unwrap_or(0);
+                Err(Error::new(
+                    span,
+                    format!(
+                        "{name} needs {n} stack item{} but only {depth} {} available (inputs = {inputs})",
+                        if n == 1 { "" } else { "s" },
+                        if depth == 1 { "is" } else { "are" },
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Sums stack item counts for `op`, rejecting literal counts too large to
+    /// add up.
+    fn items(op: u8, span: Span, counts: &[usize]) -> Result<usize> {
+        counts
+            .iter()
+            .try_fold(0usize, |sum, &n| sum.checked_add(n))
+            .ok_or_else(|| {
+                let name = opcode_name(op).unwrap_or("opcode");
+                Error::new(span, format!("{name} count overflows the stack depth"))
+            })
+    }
+
+    /// Value of the item `n` places below the top, if known.
+    fn peek(&self, n: usize) -> Option<i64> {
+        let values = &self.state.values;
+        values.len().checked_sub(n + 1).and_then(|i| values[i])
+    }
+
+    fn pop_push(&mut self, pops: usize, pushes: &[Option<i64>]) {
+        let state = &mut self.state;
+        state.depth = state.depth.map(|d| d - pops + pushes.len());
+        let keep = state.values.len().saturating_sub(pops);
+        state.values.truncate(keep);
+        state.values.extend(pushes);
+    }
+
+    fn unknown(&mut self) {
+        self.state.depth = None;
+        self.state.values.clear();
+    }
+
+    fn push_value(&mut self, value: Option<i64>) {
+        self.pop_push(0, &[value]);
+    }
+
+    fn op(&mut self, op: u8, span: Span) -> Result<()> {
+        let name = || opcode_name(op).unwrap_or("opcode");
+        match effect(op) {
+            Effect::If => {
+                if self.state.reachable {
+                    self.require(1, op, span)?;
+                    self.pop_push(1, &[]);
+                }
+                self.frames.push(Frame {
+                    span,
+                    start: self.state.clone(),
+                    ends: Vec::new(),
+                    has_else: false,
+                });
+            }
+            Effect::Else => {
+                let frame = self
+                    .frames
+                    .last_mut()
+                    .ok_or_else(|| Error::new(span, format!("{} without OP_IF", name())))?;
+                frame.ends.push(self.state.clone());
+                frame.has_else = true;
+                self.state = frame.start.clone();
+            }
+            Effect::EndIf => {
+                let mut frame = self
+                    .frames
+                    .pop()
+                    .ok_or_else(|| Error::new(span, format!("{} without OP_IF", name())))?;
+                frame.ends.push(self.state.clone());
+                if !frame.has_else {
+                    frame.ends.push(frame.start.clone());
+                }
+                self.state = merge(&frame.ends);
+            }
+            _ if !self.state.reachable => {}
+            Effect::Fixed(pops, pushes) => {
+                self.require(pops, op, span)?;
+                let value = match op {
+                    0x00 => Some(0),
+                    0x4f => Some(-1),
+                    0x51..=0x60 => Some((op - 0x50) as i64),
+                    _ => None,
+                };
+                self.pop_push(pops, &vec![value; pushes]);
+            }
+            Effect::Return => self.state.reachable = false,
+            Effect::Pick => {
+                let n = self.peek(0).and_then(|n| usize::try_from(n).ok());
+                let needed = n.map_or(Ok(2), |n| Self::items(op, span, &[n, 2]))?;
+                self.require(needed, op, span)?;
+                self.pop_push(1, &[None]);
+            }
+            Effect::Roll => {
+                let n = self.peek(0).and_then(|n| usize::try_from(n).ok());
+                let needed = n.map_or(Ok(2), |n| Self::items(op, span, &[n, 2]))?;
+                self.require(needed, op, span)?;
+                self.pop_push(1, &[]);
+                let values = &mut self.state.values;
+                match n {
+                    // The item moves to the top and everything above it
+                    // shifts down one place
+                    Some(n) if n < values.len() => {
+                        let moved = values.remove(values.len() - 1 - n);
+                        values.push(moved);
+                    }
+                    Some(_) if !values.is_empty() => {
+                        values.remove(0);
+                        values.push(None);
+                    }
+                    _ => values.clear(),
+                }
+            }
+            Effect::Depth => self.push_value(self.state.depth.map(|d| d as i64)),
+            Effect::IfDup => {
+                self.require(1, op, span)?;
+                self.unknown();
+            }
+            Effect::ToAlt => {
+                self.require(1, op, span)?;
+                self.pop_push(1, &[]);
+                self.state.alt = self.state.alt.map(|a| a + 1);
+            }
+            Effect::FromAlt => {
+                if self.state.alt == Some(0) {
+                    return Err(Error::new(
+                        span,
+                        format!("{} with an empty alt stack", name()),
+                    ));
+                }
+                self.state.alt = self.state.alt.map(|a| a - 1);
+                self.push_value(None);
+            }
+            Effect::Multisig(results) => {
+                self.require(1, op, span)?;
+                let Some(n) = self.peek(0).and_then(|n| usize::try_from(n).ok()) else {
+                    self.unknown();
+                    return Ok(());
+                };
+                self.require(Self::items(op, span, &[n, 2])?, op, span)?;
+                match self.peek(n + 1).and_then(|m| usize::try_from(m).ok()) {
+                    Some(m) => {
+                        let needed = Self::items(op, span, &[n, m, 3])?;
+                        self.require(needed, op, span)?;
+                        self.pop_push(needed, &vec![None; results]);
+                    }
+                    None => self.unknown(),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, value: Option<i64>) {
+        if self.state.reachable {
+            self.push_value(value);
+        }
+    }
+
+    /// A spliced script may do anything to either stack.
+    fn splice(&mut self) {
+        if self.state.reachable {
+            self.unknown();
+            self.state.alt = None;
+        }
+    }
+
+    fn finish(&self) -> Result<()> {
+        match self.frames.last() {
+            Some(frame) => Err(Error::new(frame.span, "OP_IF without OP_ENDIF")),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Joins the states at the end of each branch of a conditional.
+fn merge(ends: &[State]) -> State {
+    let live: Vec<&State> = ends.iter().filter(|s| s.reachable).collect();
+    let Some(first) = live.first() else {
+        return ends[0].clone();
+    };
+    let same = |f: fn(&State) -> Option<usize>| {
+        let value = f(first);
+        live.iter()
+            .all(|s| f(s) == value)
+            .then_some(value)
+            .flatten()
+    };
+    State {
+        reachable: true,
+        depth: same(|s| s.depth),
+        alt: same(|s| s.alt),
+        values: Vec::new(),
+    }
+}
+
+pub fn expand(input: ScriptInput) -> Result<TokenStream> {
+    let mut checker = Checker::new(input.inputs);
+    // Runs of literal bytes, separated by run-time expressions
+    let mut literal: Vec<u8> = Vec::new();
+    let mut parts = Vec::new();
+    for expr in &input.items {
+        match classify(expr)? {
+            Item::Op(op) => {
+                checker.op(op, expr.span())?;
+                literal.push(op);
+            }
+            Item::Push(bytes, value) => {
+                checker.push(value);
+                literal.extend(bytes);
+            }
+            Item::Runtime(expr) => {
+                checker.push(None);
+                let expr = match expr {
+                    Expr::Paren(paren) => *paren.expr,
+                    expr => expr,
+                };
+                parts.push(quote! { script.extend_from_slice(&[#(#literal),*]); });
+                parts.push(quote! { ::nprint_core::ScriptLiteral::push_to(#expr, &mut script); });
+                literal.clear();
+            }
+            Item::Splice(expr) => {
+                checker.splice();
+                parts.push(quote! { script.extend_from_slice(&[#(#literal),*]); });
+                parts.push(quote! { script.extend(#expr); });
+                literal.clear();
+            }
+        }
+    }
+    checker.finish()?;
+
+    if parts.is_empty() && literal.is_empty() {
+        return Ok(quote! { [0u8; 0] });
+    }
+    let literal = literal.into_iter().map(Literal::u8_suffixed);
+    if parts.is_empty() {
+        return Ok(quote! { [#(#literal),*] });
+    }
+    Ok(quote! {{
+        let mut script: ::std::vec::Vec<u8> = ::std::vec::Vec::new();
+        #(#parts)*
+        script.extend_from_slice(&[#(#literal),*]);
+        script
+    }})
+}
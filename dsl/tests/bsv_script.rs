@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::{Stack, drop_n, from_asm, hashcat, xswap};
+    use nprint_dsl::bsv_script;
+
+    const PREFIX: [u8; 4] = bsv_script! { OP_DUP, OP_HASH160, 20 };
+
+    #[test]
+    fn test_literal_scripts_are_arrays() {
+        assert_eq!(PREFIX, [0x76, 0xa9, 0x01, 0x14]);
+        const EMPTY: [u8; 0] = bsv_script! {};
+        assert_eq!(EMPTY, []);
+        let script = bsv_script! { 0, -1, 16, 17, 1000, hex!("abcd"), b"xy", OP_TRUE, OP_NOP2 };
+        assert_eq!(
+            script.to_vec(),
+            from_asm("OP_0 OP_1NEGATE OP_16 0x11 0xe803 0xabcd 0x7879 OP_1 OP_CHECKLOCKTIMEVERIFY")
+                .unwrap()
+        );
+        // Matches the declarative macro byte for byte
+        assert_eq!(
+            script.to_vec(),
+            nprint_core::bsv_script! { 0, -1, 16, 17, 1000, hex!("abcd"), b"xy", OP_TRUE, OP_NOP2 }
+        );
+    }
+
+    #[test]
+    fn test_runtime_items() {
+        let n = 5i64;
+        let pkh = [0xaa; 20];
+        let script: Vec<u8> = bsv_script! { inputs = 2; OP_DUP, OP_HASH160, &pkh, OP_EQUALVERIFY, OP_CHECKSIG, n, OP_DROP };
+        assert_eq!(script[..3], [0x76, 0xa9, 20]);
+        assert_eq!(script[3..23], pkh);
+        assert_eq!(script[23..], [0x88, 0xac, 0x55, 0x75]);
+
+        let script = bsv_script! { n, (n * 2), OP_ADD, 15, OP_NUMEQUAL };
+        let mut stack = Stack::default();
+        stack.execute(&script).unwrap();
+        assert_eq!(stack.main, vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_checked_scripts() {
+        // Literal PICK/ROLL depths are followed
+        let script = bsv_script! { inputs = 3; 2, OP_PICK, 3, OP_ROLL, OP_2DROP, OP_2DROP };
+        let mut stack = Stack::default();
+        for i in 0..3 {
+            stack.push(vec![i]);
+        }
+        stack.execute(&script).unwrap();
+        assert!(stack.main.is_empty());
+
+        // Both branches leave the same depth
+        let script = bsv_script! { inputs = 2; OP_IF, OP_DUP, OP_ELSE, 7, OP_ENDIF, OP_ADD };
+        assert_eq!(script.len(), 6);
+
+        // Alt stack round trip
+        let _ = bsv_script! { inputs = 1; OP_TOALTSTACK, OP_FROMALTSTACK, OP_DEPTH, OP_DROP };
+
+        // 2-of-3 multisig with the dummy element
+        let _ = bsv_script! { inputs = 3; 2, hex!("02"), hex!("03"), hex!("04"), 3, OP_CHECKMULTISIG, OP_VERIFY };
+
+        // OP_RETURN ends checking; run-time counts make the depth unknown
+        let _ = bsv_script! { inputs = 0; OP_RETURN, OP_DROP };
+        let n = 1;
+        let _ = bsv_script! { inputs = 2; n, OP_ROLL, OP_CHECKMULTISIG, OP_DROP, OP_DROP };
+        // Without a declared depth only conditionals are checked
+        let _ = bsv_script! { OP_SWAP, OP_IF, OP_ENDIF };
+
+        // A literal ROLL moves the tracked 9 to the top, leaving 0 for OP_PICK
+        let script = bsv_script! { inputs = 1; 9, 0, 1, OP_ROLL, OP_DROP, OP_PICK };
+        let mut stack = Stack::default();
+        stack.push(vec![7]);
+        stack.execute(&script).unwrap();
+        assert_eq!(stack.main, vec![vec![7], vec![7]]);
+
+        // Branches after OP_RETURN are not checked
+        let _ = bsv_script! { inputs = 0; OP_RETURN, OP_IF, OP_ENDIF };
+    }
+
+    #[test]
+    fn test_macro_splices() {
+        let script = bsv_script! { inputs = 3; xswap!(3), OP_DROP, hashcat!() };
+        assert_eq!(
+            script,
+            nprint_core::bsv_script! { 2, OP_ROLL, OP_DROP, OP_DUP, OP_HASH160, OP_CAT }
+        );
+        // Checking stops after a splice, whose effect is not known
+        let _ = bsv_script! { inputs = 0; drop_n!(2), OP_DROP };
+    }
+}
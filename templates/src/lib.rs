@@ -3,10 +3,6 @@ use nprint_types::{Artifact, Sha256, SmartContract, ToScript};
 use sha2::{Digest, Sha256 as Sha256Digest};
 use std::collections::HashMap;
 use std::vec::Vec;
-use sv::script::op_codes::{
-    OP_CAT, OP_CHECKMULTISIG, OP_CHECKSEQUENCEVERIFY, OP_CHECKSIG, OP_DROP, OP_DUP, OP_EQUAL,
-    OP_EQUALVERIFY, OP_HASH160, OP_SHA256,
-};
 
 fn compute_sha_gate(input: &Vec<u8>) -> Sha256 {
     let digest = Sha256Digest::digest(input);
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use nprint_core::{Instruction, Instructions, Interpreter, ScriptError, Stack, opcode_name};
+use serde::Serialize;
+use sv::script::op_codes::OP_PUSHDATA1;
+
+use crate::Artifact;
+
+/// Size and opcode histogram of one script.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ScriptCost {
+    /// Length in bytes.
+    pub size: usize,
+    /// Instruction count by ASM name. Direct pushes count as
+    /// `OP_PUSHBYTES_<n>` and undefined opcodes as `OP_UNKNOWN<n>`.
+    pub opcodes: BTreeMap<String, usize>,
+}
+
+impl ScriptCost {
+    /// Measures `script`; a truncated push at the end counts as one instruction.
+    pub fn of(script: &[u8]) -> Self {
+        let mut opcodes = BTreeMap::new();
+        for (_, instruction) in Instructions::new(script) {
+            *opcodes.entry(histogram_key(&instruction)).or_insert(0) += 1;
+        }
+        Self {
+            size: script.len(),
+            opcodes,
+        }
+    }
+}
+
+fn histogram_key(instruction: &Instruction) -> String {
+    let op = instruction.opcode();
+    match (opcode_name(op), instruction) {
+        (Some(name), _) => name.to_string(),
+        (None, Instruction::Push { data, .. }) => format!("OP_PUSHBYTES_{}", data.len()),
+        // Truncated direct push: the opcode is the intended length
+        (None, Instruction::Invalid(_)) if op < OP_PUSHDATA1 => format!("OP_PUSHBYTES_{op}"),
+        (None, _) => format!("OP_UNKNOWN{op}"),
+    }
+}
+
+/// Size, shape and fee estimate of an [`Artifact`], from [`Artifact::cost`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CostReport {
+    pub locking: ScriptCost,
+    pub unlocking: Option<ScriptCost>,
+    /// Deepest the main stack got while running the unlocking script, then
+    /// the locking script, without a transaction context.
+    pub max_stack_depth: usize,
+    /// Error that stopped that run early, such as a signature opcode finding
+    /// no transaction context; `max_stack_depth` only covers the steps
+    /// before it.
+    #[serde(skip)]
+    pub error: Option<ScriptError>,
+}
+
+/// Bytes of a serialized transaction output besides its script: the 8-byte
+/// value.
+const OUTPUT_OVERHEAD: usize = 8;
+
+/// Bytes of a serialized transaction input besides its script: the 36-byte
+/// outpoint and the 4-byte sequence number.
+const INPUT_OVERHEAD: usize = 40;
+
+impl CostReport {
+    /// Serialized size of an output carrying the locking script, including
+    /// its length prefix.
+    pub fn output_size(&self) -> usize {
+        OUTPUT_OVERHEAD + var_int_len(self.locking.size) + self.locking.size
+    }
+
+    /// Serialized size of an input carrying the unlocking script.
+    pub fn input_size(&self) -> Option<usize> {
+        let unlocking = self.unlocking.as_ref()?;
+        Some(INPUT_OVERHEAD + var_int_len(unlocking.size) + unlocking.size)
+    }
+
+    /// Fee share of the output at `sat_per_kb` satoshis per 1000 bytes,
+    /// rounded up. The transaction's own fields are not included.
+    pub fn deploy_fee(&self, sat_per_kb: u64) -> u64 {
+        fee(self.output_size(), sat_per_kb)
+    }
+
+    /// Fee share of the input that spends the output, if an unlocking
+    /// script was given.
+    pub fn spend_fee(&self, sat_per_kb: u64) -> Option<u64> {
+        Some(fee(self.input_size()?, sat_per_kb))
+    }
+}
+
+fn var_int_len(n: usize) -> usize {
+    match n {
+        0..0xfd => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+fn fee(size: usize, sat_per_kb: u64) -> u64 {
+    (size as u64 * sat_per_kb).div_ceil(1000)
+}
+
+impl Artifact {
+    /// Measures the locking script and, if given, the unlocking script that
+    /// spends it.
+    ///
+    /// ```
+    /// use nprint_core::bsv_script;
+    /// use nprint_types::Artifact;
+    ///
+    /// let artifact = Artifact {
+    ///     script: bsv_script! { OP_2, OP_ADD, OP_5, OP_EQUAL },
+    ///     props: vec![],
+    /// };
+    /// let report = artifact.cost(Some(&bsv_script! { OP_3 }));
+    /// assert_eq!(report.locking.size, 4);
+    /// assert_eq!(report.locking.opcodes["OP_ADD"], 1);
+    /// assert_eq!(report.max_stack_depth, 2);
+    /// assert_eq!(report.error, None);
+    /// assert_eq!(report.output_size(), 13);
+    /// assert_eq!(report.deploy_fee(500), 7);
+    /// assert_eq!(report.spend_fee(500), Some(21));
+    /// ```
+    pub fn cost(&self, unlocking: Option<&[u8]>) -> CostReport {
+        let mut stack = Stack::default();
+        let mut max_stack_depth = 0;
+        let mut error = None;
+        for script in unlocking.into_iter().chain([self.script.as_slice()]) {
+            let mut interpreter = Interpreter::new(&mut stack, script);
+            loop {
+                match interpreter.step() {
+                    Ok(Some(_)) => {
+                        max_stack_depth = max_stack_depth.max(interpreter.stack().main.len())
+                    }
+                    Ok(None) => break,
+                    Err(err) => {
+                        error = Some(err);
+                        break;
+                    }
+                }
+            }
+            if error.is_some() {
+                break;
+            }
+        }
+        CostReport {
+            locking: ScriptCost::of(&self.script),
+            unlocking: unlocking.map(ScriptCost::of),
+            max_stack_depth,
+            error,
+        }
+    }
+}
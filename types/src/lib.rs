@@ -1,6 +1,9 @@
-use nprint_core::bsv_script;
+use nprint_core::{ScriptNum, bsv_script, encode_push};
 use serde::{Deserialize, Serialize};
-use sv::script::stack::encode_num;
+
+mod cost;
+
+pub use cost::{CostReport, ScriptCost};
 
 #[derive(Serialize, Deserialize)]
 pub struct Artifact {
@@ -21,10 +24,7 @@ pub struct Sha256(pub [u8; 32]);
 
 impl ToScript for Sha256 {
     fn to_script(&self) -> Vec<u8> {
-        let mut script = Vec::new();
-        script.push(32);
-        script.extend_from_slice(&self.0);
-        script
+        encode_push(&self.0, true)
     }
 }
 
@@ -44,11 +44,13 @@ impl ToScript for i64 {
 
 impl ToScript for i128 {
     fn to_script(&self) -> Vec<u8> {
-        let bytes = encode_num(*self as i64).unwrap();
-        let mut script = Vec::new();
-        script.push(bytes.len() as u8);
-        script.extend(bytes);
-        script
+        ScriptNum::from(*self).to_push()
+    }
+}
+
+impl ToScript for ScriptNum {
+    fn to_script(&self) -> Vec<u8> {
+        self.to_push()
     }
 }
 
@@ -68,18 +70,12 @@ impl ToScript for u8 {
 
 impl ToScript for Vec<u8> {
     fn to_script(&self) -> Vec<u8> {
-        let mut script = Vec::new();
-        script.push(self.len() as u8);
-        script.extend_from_slice(self);
-        script
+        encode_push(self, true)
     }
 }
 
 impl ToScript for [u8; 20] {
     fn to_script(&self) -> Vec<u8> {
-        let mut script = Vec::new();
-        script.push(20);
-        script.extend_from_slice(self);
-        script
+        encode_push(self, true)
     }
 }
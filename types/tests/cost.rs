@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::{ScriptErrorKind, bsv_script};
+    use nprint_types::{Artifact, ScriptCost};
+
+    fn artifact(script: Vec<u8>) -> Artifact {
+        Artifact {
+            script,
+            props: vec![],
+        }
+    }
+
+    #[test]
+    fn test_opcode_histogram() {
+        let script = bsv_script! { OP_DUP, OP_HASH160, hex!("abcd"), OP_EQUALVERIFY, OP_DUP, OP_0 };
+        let cost = ScriptCost::of(&script);
+        assert_eq!(cost.size, 8);
+        let counts: Vec<_> = cost.opcodes.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(
+            counts,
+            [
+                ("OP_0", 1),
+                ("OP_DUP", 2),
+                ("OP_EQUALVERIFY", 1),
+                ("OP_HASH160", 1),
+                ("OP_PUSHBYTES_2", 1),
+            ]
+        );
+
+        let cost = ScriptCost::of(&[0xba, 0x4c, 0x05, 0x01]);
+        assert_eq!(cost.opcodes["OP_UNKNOWN186"], 1);
+        assert_eq!(cost.opcodes["OP_PUSHDATA1"], 1);
+        assert_eq!(ScriptCost::of(&[0x05, 0x01]).opcodes["OP_PUSHBYTES_5"], 1);
+        assert!(ScriptCost::of(&[]).opcodes.is_empty());
+    }
+
+    #[test]
+    fn test_cost_report() {
+        let locking = bsv_script! { OP_DUP, OP_ADD, OP_DUP, OP_DUP, OP_2DROP };
+        let report = artifact(locking).cost(Some(&bsv_script! { OP_1, OP_2 }));
+        assert_eq!(report.locking.size, 5);
+        assert_eq!(report.unlocking.as_ref().unwrap().size, 2);
+        assert_eq!(report.max_stack_depth, 4);
+        assert_eq!(report.error, None);
+        // 8-byte value, 1-byte length; outpoint, sequence and 1-byte length
+        assert_eq!(report.output_size(), 14);
+        assert_eq!(report.input_size(), Some(43));
+        assert_eq!(report.deploy_fee(1000), 14);
+        assert_eq!(report.spend_fee(1000), Some(43));
+        assert_eq!(report.deploy_fee(1), 1);
+        assert_eq!(report.deploy_fee(0), 0);
+
+        // Without an unlocking script the locking script runs on its own
+        let report = artifact(bsv_script! { OP_1, OP_DUP, OP_DUP, OP_CHECKSIG }).cost(None);
+        assert_eq!(report.unlocking, None);
+        assert_eq!(report.spend_fee(1000), None);
+        assert_eq!(report.max_stack_depth, 3);
+        assert_eq!(report.error.unwrap().kind, ScriptErrorKind::MissingContext);
+
+        // Length prefixes grow with the script
+        let report = artifact(vec![0x61; 300]).cost(None);
+        assert_eq!(report.output_size(), 8 + 3 + 300);
+        assert_eq!(report.locking.opcodes["OP_NOP"], 300);
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod tests {
+    use nprint_core::Stack;
+    use nprint_types::{Sha256, ToScript};
+    use sv::script::op_codes::{OP_0, OP_1NEGATE, OP_5, OP_PUSHDATA1, OP_PUSHDATA2};
+
+    fn pushed(script: &[u8]) -> Vec<Vec<u8>> {
+        let mut stack = Stack::default();
+        stack.execute(script).unwrap();
+        stack.main
+    }
+
+    #[test]
+    fn test_byte_pushes() {
+        for len in [0, 1, 20, 75, 76, 255, 256, 1000] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 200) as u8 + 17).collect();
+            assert_eq!(pushed(&data.to_script()), vec![data], "length {len}");
+        }
+        assert_eq!(vec![0x42; 80].to_script()[..2], [OP_PUSHDATA1, 80]);
+        assert_eq!(vec![0x42; 300].to_script()[..3], [OP_PUSHDATA2, 0x2c, 0x01]);
+
+        // Minimal pushes
+        assert_eq!(Vec::new().to_script(), vec![OP_0]);
+        assert_eq!(vec![5].to_script(), vec![OP_5]);
+        assert_eq!(vec![0x81].to_script(), vec![OP_1NEGATE]);
+
+        let hash = Sha256([7; 32]);
+        assert_eq!(pushed(&hash.to_script()), vec![vec![7; 32]]);
+        assert_eq!(pushed(&[9; 20].to_script()), vec![vec![9; 20]]);
+    }
+}
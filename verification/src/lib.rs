@@ -1,4 +1,8 @@
-use nprint_core::{MacroDef, Stack, expand_macro};
+use core::ops::RangeInclusive;
+use nprint_core::{
+    ExecutionContext, MacroDef, MacroError, MacroRegistry, ScriptError, ScriptErrorKind,
+    ScriptFlags, ScriptNum, Stack, StackEffect, decode_bool, expand_macro,
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,23 +13,209 @@ pub enum VerifyError {
     InvalidOp,
     #[error("Verification failed")]
     Failed,
+    #[error("Script error: {0}")]
+    Script(ScriptError),
+    #[error("Macro error: {0}")]
+    Macro(#[from] MacroError),
+    #[error("Stack effect violated with arguments {args:?}: {violation}")]
+    Effect {
+        args: Vec<i32>,
+        violation: EffectViolation,
+    },
 }
 
+/// How an expansion failed to match its macro's declared stack effect.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EffectViolation {
+    #[error("no stack effect declared")]
+    Undeclared,
+    #[error("declared input or output count is negative")]
+    NegativeCount,
+    #[error("expansion failed: {0}")]
+    Macro(MacroError),
+    #[error("execution failed: {0}")]
+    Script(ScriptError),
+    #[error("changed items below its declared inputs")]
+    Clobbered,
+    #[error("read items below its declared inputs")]
+    ReadBelow,
+    #[error("left {found} items where {expected} were declared")]
+    Outputs { expected: usize, found: usize },
+    #[error("left items on the alt stack")]
+    AltStack,
+}
+
+/// Items placed below the declared inputs by [`verify_effect`] to catch
+/// expansions reaching deeper than they declare. Each expansion runs on both
+/// sets; outputs that differ betray a read below the inputs.
+const SENTINELS: [[[u8; 2]; 2]; 2] = [[[0xa5, 0], [0xa5, 1]], [[0x5a, 0], [0x5a, 1]]];
+
+impl From<ScriptError> for VerifyError {
+    fn from(err: ScriptError) -> Self {
+        match err.kind {
+            ScriptErrorKind::StackUnderflow | ScriptErrorKind::AltStackUnderflow => Self::Underflow,
+            ScriptErrorKind::InvalidOpcode | ScriptErrorKind::DisabledOpcode => Self::InvalidOp,
+            ScriptErrorKind::VerifyFailed => Self::Failed,
+            _ => Self::Script(err),
+        }
+    }
+}
+
+/// Expands `def` and executes it on `inputs`. If the macro declares a stack
+/// effect, the items below its inputs must be left alone and the declared
+/// number of outputs must take the place of its inputs.
 pub fn verify_macro(def: &MacroDef, args: &[i32], inputs: Vec<Vec<u8>>) -> Result<(), VerifyError> {
-    let script = expand_macro(def, args);
+    let script = expand_macro(def, args)?;
+    let Some(effect) = &def.effect else {
+        let mut stack = Stack::default();
+        for input in inputs {
+            stack.push(input);
+        }
+        stack.execute(&script)?;
+        return Ok(());
+    };
+    let effect_error = |violation| VerifyError::Effect {
+        args: args.to_vec(),
+        violation,
+    };
+    let (consumed, produced) = declared_counts(def, effect, args).map_err(effect_error)?;
+    if inputs.len() < consumed {
+        return Err(VerifyError::Underflow);
+    }
+    check_effect(&script, inputs, consumed, produced)
+        .map(|_| ())
+        .map_err(|violation| match violation {
+            EffectViolation::Script(err) => err.into(),
+            violation => effect_error(violation),
+        })
+}
+
+/// Checks `def` against its declared stack effect for every combination of
+/// arguments drawn from `params`, one range per parameter, resolving macro
+/// references through `registry`.
+///
+/// Each expansion runs on as many inputs as declared, the script numbers 1,
+/// 2, ... from the bottom up, above a few sentinel items. It must execute,
+/// leave the sentinels untouched, leave the declared number of outputs above
+/// them and leave the alt stack empty, and its outputs must not depend on
+/// the sentinels' values. The first failing argument list is
+/// returned in [`VerifyError::Effect`].
+pub fn verify_effect(
+    registry: &MacroRegistry,
+    def: &MacroDef,
+    params: &[RangeInclusive<i32>],
+) -> Result<(), VerifyError> {
+    let mut args: Vec<i32> = params.iter().map(|r| *r.start()).collect();
+    if params.iter().any(|r| r.is_empty()) {
+        return Ok(());
+    }
+    loop {
+        check_args(registry, def, &args).map_err(|violation| VerifyError::Effect {
+            args: args.clone(),
+            violation,
+        })?;
+        // Advance like an odometer, the last parameter fastest
+        let Some(i) = (0..args.len()).rev().find(|&i| args[i] < *params[i].end()) else {
+            return Ok(());
+        };
+        args[i] += 1;
+        for (arg, range) in args.iter_mut().zip(params).skip(i + 1) {
+            *arg = *range.start();
+        }
+    }
+}
+
+fn check_args(
+    registry: &MacroRegistry,
+    def: &MacroDef,
+    args: &[i32],
+) -> Result<(), EffectViolation> {
+    let effect = def.effect.as_ref().ok_or(EffectViolation::Undeclared)?;
+    let (consumed, produced) = declared_counts(def, effect, args)?;
+    let script = registry
+        .expand_def(def, args)
+        .map_err(EffectViolation::Macro)?;
+    let run = |sentinels: &[[u8; 2]; 2]| {
+        let inputs = sentinels
+            .iter()
+            .map(|s| s.to_vec())
+            .chain((1..=consumed as i64).map(|n| ScriptNum::from(n).encode()))
+            .collect();
+        check_effect(&script, inputs, consumed, produced)
+    };
+    if run(&SENTINELS[0])? != run(&SENTINELS[1])? {
+        return Err(EffectViolation::ReadBelow);
+    }
+    Ok(())
+}
+
+fn declared_counts(
+    def: &MacroDef,
+    effect: &StackEffect,
+    args: &[i32],
+) -> Result<(usize, usize), EffectViolation> {
+    let (consumed, produced) = effect.eval(args).map_err(|kind| {
+        EffectViolation::Macro(MacroError {
+            kind,
+            name: def.name.clone(),
+        })
+    })?;
+    let count = |n: i32| usize::try_from(n).map_err(|_| EffectViolation::NegativeCount);
+    Ok((count(consumed)?, count(produced)?))
+}
+
+/// Runs `script` on `inputs`, of which the top `consumed` may be replaced by
+/// `produced` new items, and returns those outputs.
+fn check_effect(
+    script: &[u8],
+    inputs: Vec<Vec<u8>>,
+    consumed: usize,
+    produced: usize,
+) -> Result<Vec<Vec<u8>>, EffectViolation> {
+    let below = inputs.len() - consumed;
+    let untouched = inputs[..below].to_vec();
     let mut stack = Stack::default();
     for input in inputs {
         stack.push(input);
     }
-    stack.execute(&script).map_err(|_| VerifyError::InvalidOp)?;
-    Ok(())
+    stack.execute(script).map_err(EffectViolation::Script)?;
+    if stack.main.get(..below) != Some(&untouched[..]) {
+        return Err(EffectViolation::Clobbered);
+    }
+    if stack.main.len() != below + produced {
+        return Err(EffectViolation::Outputs {
+            expected: produced,
+            found: stack.main.len() - below,
+        });
+    }
+    if !stack.alt.is_empty() {
+        return Err(EffectViolation::AltStack);
+    }
+    Ok(stack.main.split_off(below))
 }
 
 pub fn verify_script(script: &[u8], inputs: Vec<Vec<u8>>) -> Result<bool, VerifyError> {
-    let mut stack = Stack::default();
+    verify_script_with_flags(script, inputs, ScriptFlags::default())
+}
+
+/// Verifies a script under the given rule set, e.g. consensus rather than policy.
+pub fn verify_script_with_flags(
+    script: &[u8],
+    inputs: Vec<Vec<u8>>,
+    flags: ScriptFlags,
+) -> Result<bool, VerifyError> {
+    let mut stack = Stack {
+        flags,
+        ..Default::default()
+    };
     for input in inputs {
         stack.push(input);
     }
-    stack.execute(script).map_err(|_| VerifyError::InvalidOp)?;
-    Ok(!stack.main.is_empty() && !stack.main.last().unwrap().is_empty())
+    stack.execute(script)?;
+    Ok(stack.main.last().is_some_and(|top| decode_bool(top)))
+}
+
+/// Verifies that the context's input unlocks its locking script.
+pub fn verify_spend(ctx: &ExecutionContext) -> Result<bool, VerifyError> {
+    Ok(ctx.verify()?)
 }
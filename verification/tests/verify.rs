@@ -1,6 +1,23 @@
-use nprint_core::{MacroDef, MacroElem, Stack, bsv_script};
-use nprint_verification::{verify_macro, verify_script};
-use sv::script::op_codes::{OP_DUP, OP_PICK};
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use nprint_core::{
+    ExecutionContext, MacroDef, MacroElem, MacroErrorKind, MacroParseError, MacroRegistry,
+    SIGHASH_ALL, SIGHASH_FORKID, ScriptFlags, Stack, StackEffect, Transaction, TxInput, TxOutput,
+    bsv_script, std_macros,
+};
+use nprint_templates::{Hashlock, Multisig, P2PKH, SHAGate, Timelock};
+use nprint_types::{Sha256, SmartContract};
+use nprint_verification::{
+    EffectViolation, VerifyError, verify_effect, verify_macro, verify_script,
+    verify_script_with_flags, verify_spend,
+};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256 as Sha256Digest};
+use std::ops::RangeInclusive;
+use sv::script::op_codes::{
+    OP_1, OP_1ADD, OP_2, OP_3, OP_6, OP_ADD, OP_EQUAL, OP_INVERT, OP_LSHIFT, OP_MUL, OP_PICK,
+    OP_RSHIFT,
+};
 
 #[cfg(test)]
 mod tests {
@@ -12,6 +29,7 @@ mod tests {
             name: "xswap".to_string(),
             param_count: 1,
             template: vec![MacroElem::Param(0), MacroElem::Op(OP_PICK)],
+            effect: None,
         }; // Simplified
         let inputs = vec![vec![1], vec![2], vec![3]];
         let script = bsv_script! { OP_DUP };
@@ -19,7 +37,355 @@ mod tests {
         for input in inputs.clone() {
             stack.push(input);
         }
-        assert!(verify_macro(&def, &[3], inputs).is_ok());
+        assert!(verify_macro(&def, &[2], inputs.clone()).is_ok());
+        assert!(matches!(
+            verify_macro(&def, &[], inputs),
+            Err(VerifyError::Macro(err)) if err.kind == MacroErrorKind::ArgCountMismatch
+        ));
         assert!(verify_script(&script, stack.main).unwrap());
     }
+
+    #[test]
+    fn test_verify_nmacro_file() {
+        let mut registry = MacroRegistry::new();
+        registry
+            .load_file(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/data/stack.nmacro"
+            ))
+            .unwrap();
+        let inputs = vec![vec![1], vec![2], vec![3]];
+        for name in ["xrot", "xswap"] {
+            assert!(verify_macro(registry.get(name).unwrap(), &[2], inputs.clone()).is_ok());
+        }
+        assert!(verify_macro(registry.get("xrot").unwrap(), &[3], inputs.clone()).is_err());
+        let script = registry.expand("xdrop", &[2]).unwrap();
+        assert!(verify_script(&script, inputs.clone()).unwrap());
+        let script = registry.expand("sum_is", &[5]).unwrap();
+        assert!(verify_script(&script, inputs).unwrap());
+
+        let err = MacroRegistry::new()
+            .load_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/verify.rs"))
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.get_ref().unwrap().is::<MacroParseError>());
+    }
+
+    #[test]
+    fn test_verify_effect() {
+        let mut registry = MacroRegistry::new();
+        registry
+            .load_file(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/data/stack.nmacro"
+            ))
+            .unwrap();
+        let check = |name: &str, params: &[RangeInclusive<i32>]| {
+            verify_effect(&registry, registry.get(name).unwrap(), params)
+        };
+        assert!(check("xrot", &[0..=10]).is_ok());
+        assert!(check("xswap", &[1..=10]).is_ok());
+        assert!(check("xdrop", &[1..=10]).is_ok());
+        assert!(check("sum_is", &[3..=3]).is_ok());
+
+        // 0 ROLL is a no-op, so the first counterexample is 1
+        let err = check("short_rot", &[0..=10]).unwrap_err();
+        assert!(matches!(
+            err,
+            VerifyError::Effect { ref args, violation: EffectViolation::Clobbered } if *args == [1]
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Stack effect violated with arguments [1]: changed items below its declared inputs"
+        );
+        // 0 SUB ROLL is -1 ROLL
+        assert!(matches!(
+            check("xswap", &[0..=3]),
+            Err(VerifyError::Effect {
+                violation: EffectViolation::Script(_),
+                ..
+            })
+        ));
+        // 1 + 2 != 4 leaves false, which is still one output
+        assert!(check("sum_is", &[4..=4]).is_ok());
+
+        let mut def = MacroDef {
+            name: "dup_pair".to_string(),
+            param_count: 2,
+            template: vec![
+                MacroElem::Param(0),
+                MacroElem::Op(OP_PICK),
+                MacroElem::Param(1),
+                MacroElem::Op(OP_PICK),
+            ],
+            effect: Some(StackEffect::fixed(3, 5)),
+        };
+        assert!(verify_effect(&registry, &def, &[0..=2, 0..=3]).is_ok());
+        // The second PICK reaches a sentinel once the copy sits above the inputs
+        assert!(matches!(
+            verify_effect(&registry, &def, &[0..=2, 0..=4]),
+            Err(VerifyError::Effect {
+                args,
+                violation: EffectViolation::ReadBelow,
+            }) if args == [0, 4]
+        ));
+        def.effect = Some(StackEffect::fixed(3, 4));
+        assert!(matches!(
+            verify_effect(&registry, &def, &[2..=2, 0..=2]),
+            Err(VerifyError::Effect {
+                args,
+                violation: EffectViolation::Outputs { expected: 4, found: 5 },
+            }) if args == [2, 0]
+        ));
+        def.effect = None;
+        assert!(matches!(
+            verify_effect(&registry, &def, &[0..=0, 0..=0]),
+            Err(VerifyError::Effect {
+                violation: EffectViolation::Undeclared,
+                ..
+            })
+        ));
+        def.effect = Some(StackEffect::fixed(2, -1));
+        assert!(matches!(
+            verify_effect(&registry, &def, &[0..=0, 0..=0]),
+            Err(VerifyError::Effect {
+                violation: EffectViolation::NegativeCount,
+                ..
+            })
+        ));
+
+        // The standard library's stack shuffling macros
+        let std = std_macros();
+        for name in ["xswap", "xdrop", "xdup", "drop_n"] {
+            assert!(verify_effect(&std, std.get(name).unwrap(), &[1..=8]).is_ok());
+        }
+        for name in ["xrot", "xpick"] {
+            assert!(verify_effect(&std, std.get(name).unwrap(), &[0..=8]).is_ok());
+        }
+        assert!(matches!(
+            verify_effect(&std, std.get("alt_push_n").unwrap(), &[1..=1]),
+            Err(VerifyError::Effect {
+                violation: EffectViolation::AltStack,
+                ..
+            })
+        ));
+
+        // verify_macro checks a declared effect on the given inputs
+        let inputs = vec![vec![7], vec![1], vec![2], vec![3]];
+        let xswap = registry.get("xswap").unwrap();
+        assert!(verify_macro(xswap, &[3], inputs.clone()).is_ok());
+        assert!(matches!(
+            verify_macro(registry.get("short_rot").unwrap(), &[3], inputs.clone()),
+            Err(VerifyError::Effect {
+                violation: EffectViolation::Clobbered,
+                ..
+            })
+        ));
+        assert!(matches!(
+            verify_macro(xswap, &[5], inputs),
+            Err(VerifyError::Underflow)
+        ));
+    }
+
+    #[test]
+    fn test_verify_hashlock() {
+        let preimage = b"secret_message".to_vec();
+        let hash = Sha256(Sha256Digest::digest(&preimage).into());
+        let artifact = Hashlock { hash }.compile();
+        assert!(verify_script(&artifact.script, vec![preimage]).unwrap());
+        assert!(!verify_script(&artifact.script, vec![b"wrong".to_vec()]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_sha_gate() {
+        let (left, right) = (b"left".to_vec(), b"right".to_vec());
+        let hash = Sha256(Sha256Digest::digest(b"leftright").into());
+        let artifact = SHAGate { hash }.compile();
+        assert!(verify_script(&artifact.script, vec![left.clone(), right.clone()]).unwrap());
+        assert!(!verify_script(&artifact.script, vec![right, left]).unwrap());
+    }
+
+    #[test]
+    fn test_template_sizes() {
+        // Size budgets: raise one only when a template deliberately grows
+        let hash = Sha256([7; 32]);
+        let budgets = [
+            (P2PKH { pkh: [1; 20] }.compile(), 25),
+            (Hashlock { hash }.compile(), 35),
+            (SHAGate { hash }.compile(), 36),
+            (Timelock { timeout: 144 }.compile(), 5),
+        ];
+        for (artifact, budget) in budgets {
+            let report = artifact.cost(None);
+            assert!(
+                report.locking.size <= budget,
+                "{} bytes over a budget of {budget}",
+                report.locking.size
+            );
+        }
+
+        let preimage = b"secret_message".to_vec();
+        let hash = Sha256(Sha256Digest::digest(&preimage).into());
+        let unlocking = bsv_script! { &preimage[..] };
+        let report = Hashlock { hash }.compile().cost(Some(&unlocking));
+        assert_eq!(report.error, None);
+        assert_eq!(report.max_stack_depth, 2);
+        assert_eq!(report.spend_fee(1000), Some(40 + 1 + 15));
+        assert_eq!(report.deploy_fee(1000), 8 + 1 + 35);
+    }
+
+    fn spend(locking_script: &[u8], sign: impl Fn(&[u8; 32]) -> Vec<u8>) -> bool {
+        let mut tx = Transaction {
+            version: 1,
+            inputs: vec![TxInput::default()],
+            outputs: vec![TxOutput {
+                value: 900,
+                script_pubkey: vec![],
+            }],
+            lock_time: 0,
+        };
+        let hash = tx.sighash(0, locking_script, 1000, SIGHASH_ALL | SIGHASH_FORKID);
+        tx.inputs[0].script_sig = sign(&hash);
+        let ctx = ExecutionContext {
+            tx: &tx,
+            input_index: 0,
+            amount: 1000,
+            locking_script,
+            flags: ScriptFlags::default(),
+        };
+        verify_spend(&ctx).unwrap()
+    }
+
+    fn signature_push(key: &SigningKey, hash: &[u8; 32]) -> Vec<u8> {
+        let sig: Signature = key.sign_prehash(hash).unwrap();
+        let mut bytes = sig.to_der().as_bytes().to_vec();
+        bytes.push(SIGHASH_ALL | SIGHASH_FORKID);
+        [vec![bytes.len() as u8], bytes].concat()
+    }
+
+    fn pubkey(key: &SigningKey) -> Vec<u8> {
+        key.verifying_key()
+            .to_encoded_point(true)
+            .as_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn test_verify_p2pkh_spend() {
+        let key = SigningKey::from_slice(&[1; 32]).unwrap();
+        let pk = pubkey(&key);
+        let pkh = Ripemd160::digest(Sha256Digest::digest(&pk)).into();
+        let artifact = P2PKH { pkh }.compile();
+        assert!(spend(&artifact.script, |hash| {
+            [signature_push(&key, hash), vec![pk.len() as u8], pk.clone()].concat()
+        }));
+        let other = SigningKey::from_slice(&[2; 32]).unwrap();
+        assert!(!spend(&artifact.script, |hash| {
+            [
+                signature_push(&other, hash),
+                vec![pk.len() as u8],
+                pk.clone(),
+            ]
+            .concat()
+        }));
+    }
+
+    #[test]
+    fn test_verify_multisig_spend() {
+        let keys: Vec<_> = (1..=3)
+            .map(|i| SigningKey::from_slice(&[i; 32]).unwrap())
+            .collect();
+        let artifact = Multisig {
+            pubkeys: keys.iter().map(pubkey).collect(),
+            m: 2,
+        }
+        .compile();
+        assert!(spend(&artifact.script, |hash| {
+            [
+                vec![0],
+                signature_push(&keys[0], hash),
+                signature_push(&keys[2], hash),
+            ]
+            .concat()
+        }));
+        // Signatures must follow key order
+        assert!(!spend(&artifact.script, |hash| {
+            [
+                vec![0],
+                signature_push(&keys[2], hash),
+                signature_push(&keys[0], hash),
+            ]
+            .concat()
+        }));
+    }
+
+    #[test]
+    fn test_verify_timelock_rule_sets() {
+        let artifact = Timelock { timeout: 144 }.compile();
+        let check = |sequence: u32, flags: ScriptFlags| {
+            let tx = Transaction {
+                version: 2,
+                inputs: vec![TxInput {
+                    script_sig: vec![0x51],
+                    sequence,
+                    ..Default::default()
+                }],
+                outputs: vec![],
+                lock_time: 0,
+            };
+            let ctx = ExecutionContext {
+                tx: &tx,
+                input_index: 0,
+                amount: 1000,
+                locking_script: &artifact.script,
+                flags,
+            };
+            verify_spend(&ctx)
+        };
+        assert!(check(144, ScriptFlags::PRE_GENESIS).unwrap());
+        assert!(check(143, ScriptFlags::PRE_GENESIS).is_err());
+        // Post-Genesis the relative lock is not enforced
+        assert!(check(143, ScriptFlags::GENESIS_CONSENSUS).unwrap());
+    }
+
+    #[test]
+    fn test_verify_script_rule_sets() {
+        // Five-byte operands are only valid after Genesis
+        let script = [OP_1ADD, OP_1, OP_ADD];
+        let input = vec![vec![0xff, 0xff, 0xff, 0xff, 0x00]];
+        assert!(
+            verify_script_with_flags(&script, input.clone(), ScriptFlags::GENESIS_POLICY).unwrap()
+        );
+        assert!(
+            verify_script_with_flags(&script, input.clone(), ScriptFlags::GENESIS_CONSENSUS)
+                .unwrap()
+        );
+        assert!(verify_script_with_flags(&script, input, ScriptFlags::PRE_GENESIS).is_err());
+
+        // Zero and negative zero results are false, as in ExecutionContext::verify
+        for top in [vec![], vec![0x00], vec![0x80], vec![0x00, 0x80]] {
+            assert!(!verify_script(&[], vec![top.clone()]).unwrap(), "{top:?}");
+        }
+        assert!(verify_script(&[], vec![vec![0x00, 0x01]]).unwrap());
+
+        // Opcodes re-enabled at Genesis are disabled before it
+        for script in [
+            [OP_2, OP_3, OP_MUL, OP_6, OP_EQUAL],
+            [OP_1, OP_1, OP_LSHIFT, OP_2, OP_EQUAL],
+            [OP_2, OP_1, OP_RSHIFT, OP_1, OP_EQUAL],
+            [OP_1, OP_INVERT, 0x01, 0xfe, OP_EQUAL],
+        ] {
+            assert!(
+                verify_script_with_flags(&script, vec![], ScriptFlags::GENESIS_CONSENSUS).unwrap(),
+                "{script:?}"
+            );
+            assert!(
+                matches!(
+                    verify_script_with_flags(&script, vec![], ScriptFlags::PRE_GENESIS),
+                    Err(VerifyError::InvalidOp)
+                ),
+                "{script:?}"
+            );
+        }
+    }
 }